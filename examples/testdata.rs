@@ -1,5 +1,7 @@
 extern crate cayley;
 
+use cayley::{Graph, Quad};
+
 fn main() {
     // let graph = cayley::Graph::new("localhost", 64210);
     //
@@ -9,12 +11,12 @@ fn main() {
     //
     // graph.vertex("dani").out("follows").all();
 
-    // graph.write([ { subject: "Subject Node",
-    //                 predicate: "Predicate Node",
-    //                 object: "Object Node" }]);
-    //
-    // graph.delete([ { subject: "Subject Node",
-    //                 predicate: "Predicate Node",
-    //                 object: "Object Node" }]);
+    let graph = Graph::default().unwrap();
+
+    let quads = [Quad::new("Subject Node", "Predicate Node", "Object Node")];
+
+    println!("{}", graph.write(quads.as_slice()).unwrap());
+
+    println!("{}", graph.delete(quads.as_slice()).unwrap());
 
 }
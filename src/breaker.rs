@@ -0,0 +1,108 @@
+use std::cmp;
+use std::time::Duration;
+use std::sync::{Arc, Mutex};
+
+use time;
+
+use errors::{GraphResult, CircuitOpen};
+
+/// Stops sending requests to Cayley when it seems to be down, pass it to
+/// `graph.with_breaker(...)`
+///
+/// The breaker counts the requests which failed in a row with the error which
+/// [is_retryable](../errors/enum.GraphRequestError.html#method.is_retryable), other errors, like
+/// the rejected queries, tell that the server is alive. After `failure_threshold` such failures
+/// the breaker opens, and every request fails at once with `CircuitOpen` error, without waiting
+/// for the server. When `cool_down` passes, the breaker lets a single request through to probe
+/// the server: it is closed again if the probe succeeds, and opened for one more cool-down if not.
+/// If the probe gives no answer for one more `cool_down`, the next request probes the server
+/// instead of it.
+///
+/// Clones of the breaker share its state, so a single breaker may protect several graphs
+/// talking to the same Cayley, or several tasks using their own graphs.
+///
+/// ```
+/// use std::time::Duration;
+/// use cayley::Graph;
+/// use cayley::breaker::CircuitBreaker;
+///
+/// let graph = Graph::default().unwrap()
+///                   .with_breaker(CircuitBreaker::new(5, Duration::seconds(10)));
+/// ```
+#[deriving(Clone)]
+pub struct CircuitBreaker {
+    failure_threshold: uint,
+    cool_down: Duration,
+    state: Arc<Mutex<CircuitState>>
+}
+
+/// The state of the [CircuitBreaker](./struct.CircuitBreaker.html)
+#[deriving(Clone, PartialEq, Show)]
+pub enum CircuitState {
+    /// Requests are sent, holds the number of the failures in a row
+    Closed(uint),
+    /// Requests fail at once, holds the time the breaker is half-opened after,
+    /// in nanoseconds, see `time::precise_time_ns`
+    Open(u64),
+    /// A single request is sent to probe the server, the others fail at once, holds the time
+    /// the next request is let through after, if the probe is not answered, in nanoseconds
+    HalfOpen(u64)
+}
+
+impl CircuitBreaker {
+
+    /// Create a closed breaker, which opens after `failure_threshold` failures in a row and
+    /// probes the server once `cool_down` passes
+    pub fn new(failure_threshold: uint, cool_down: Duration) -> CircuitBreaker {
+        CircuitBreaker { failure_threshold: cmp::max(failure_threshold, 1),
+                         cool_down: cool_down,
+                         state: Arc::new(Mutex::new(Closed(0))) }
+    }
+
+    /// The current state of the breaker
+    pub fn state(&self) -> CircuitState {
+        self.state.lock().clone()
+    }
+
+    /// Call the operation, if the breaker is closed or it is time to probe the server,
+    /// and count its result; `url` is where the request goes, reported with `CircuitOpen` error
+    pub fn call<T>(&self, url: &str, operation: || -> GraphResult<T>) -> GraphResult<T> {
+        try!(self.check(url));
+        let result = operation();
+        self.record(&result);
+        result
+    }
+
+    // fails with `CircuitOpen` error, if the request should not be sent now
+    fn check(&self, url: &str) -> GraphResult<()> {
+        let mut state = self.state.lock();
+        let now = time::precise_time_ns();
+        match *state {
+            Closed(_) => Ok(()),
+            Open(until) | HalfOpen(until) if now >= until => {
+                *state = HalfOpen(now + self.cool_down_ns());
+                Ok(())
+            },
+            Open(_) | HalfOpen(_) => Err(CircuitOpen(url.to_string()))
+        }
+    }
+
+    // counts the result of the request which passed `check(...)`
+    fn record<T>(&self, result: &GraphResult<T>) {
+        let healthy = match *result {
+            Ok(_) => true,
+            Err(ref error) => !error.is_retryable()
+        };
+        let mut state = self.state.lock();
+        *state = match (healthy, state.clone()) {
+            (true, _) => Closed(0),
+            (false, Closed(failures)) if failures + 1 < self.failure_threshold => Closed(failures + 1),
+            (false, _) => Open(time::precise_time_ns() + self.cool_down_ns())
+        };
+    }
+
+    fn cool_down_ns(&self) -> u64 {
+        cmp::max(self.cool_down.num_milliseconds(), 0) as u64 * 1_000_000
+    }
+
+}
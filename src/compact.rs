@@ -0,0 +1,234 @@
+use std::collections::HashMap;
+
+use serialize::json;
+
+use graph::GraphNode;
+use events::{ResultEvents, NodeStart, NodeTag, NodeEnd, NodeId, NullResult,
+             SyntaxError, NestedValue};
+
+use errors::{GraphResult, DecodingFailed, ResponseParseFailed};
+
+/// A compact alternative to [GraphNodes](../graph/struct.GraphNodes.html), returned from
+/// `graph.find_compact(<FinalizedQuery>)`
+///
+/// Every tag name is stored once for the whole result, and all the values are stored in
+/// a single buffer, so the nodes found hold no strings of their own, just the offsets.
+/// This saves a lot of memory and allocations on the queries which return many nodes.
+///
+/// Nodes are the objects Cayley returned in the `result` of the response, at any depth, so
+/// it fits every finalizer: a bare id (for `.ToArray()` or `.ToValue()`) is read as a node
+/// with the only `id` tag. When Cayley replied with `null` instead of the result, there are
+/// no nodes, and `is_null()` tells it apart from an empty result.
+///
+/// ```
+/// use cayley::graph::Graph;
+/// use cayley::path::{Vertex, Query};
+/// use cayley::selector::AnyNode;
+///
+/// let graph = Graph::default().unwrap();
+/// let nodes = graph.find_compact(Vertex::start(AnyNode).All()).unwrap();
+/// for node in nodes.iter() {
+///     println!("{}", node.get("id"));
+/// }
+/// ```
+pub struct CompactNodes {
+    // every tag name met in the result, once
+    keys: Vec<String>,
+    // every value met in the result, one after another
+    buffer: String,
+    // tags of all the nodes: index of the name in `keys`, start and end of the value in `buffer`
+    tags: Vec<(uint, uint, uint)>,
+    // start and end of every node in `tags`
+    nodes: Vec<(uint, uint)>,
+    // `true` if the result was `null`
+    null: bool
+}
+
+/// A single node of [CompactNodes](./struct.CompactNodes.html), borrows its tags from them
+pub struct CompactNode<'a> {
+    owner: &'a CompactNodes,
+    tags: &'a [(uint, uint, uint)]
+}
+
+/// Iterator over the nodes of [CompactNodes](./struct.CompactNodes.html)
+pub struct CompactNodesIter<'a> {
+    owner: &'a CompactNodes,
+    position: uint
+}
+
+/// Iterator over the `(tag, value)` pairs of a [CompactNode](./struct.CompactNode.html)
+pub struct CompactTags<'a> {
+    owner: &'a CompactNodes,
+    tags: &'a [(uint, uint, uint)],
+    position: uint
+}
+
+// collects nodes while the response is parsed
+struct Builder {
+    keys: HashMap<String, uint>,
+    nodes: CompactNodes,
+    // start of the current node in `tags`, if a node is being read
+    node_start: Option<uint>
+}
+
+impl CompactNodes {
+
+    /// Read nodes from the Cayley response, a JSON document with the `result` field
+    pub fn from_json(source: &str) -> GraphResult<CompactNodes> {
+        let mut builder = Builder::new();
+        for event in ResultEvents::new(source.chars()) {
+            match event {
+                Ok(NodeStart) => builder.start_node(),
+                Ok(NodeTag(key, value)) => builder.add_tag(key.as_slice(), value.as_slice()),
+                Ok(NodeEnd) => builder.end_node(),
+                Ok(NodeId(id)) => {
+                    builder.start_node();
+                    builder.add_tag("id", id.as_slice());
+                    builder.end_node();
+                },
+                Ok(NullResult) => builder.null_result(),
+                Err(SyntaxError(error)) =>
+                    return Err(DecodingFailed(json::ParseError(error), source.to_string())),
+                Err(NestedValue) => return Err(ResponseParseFailed)
+            }
+        }
+        Ok(builder.finish())
+    }
+
+    /// Number of nodes
+    pub fn len(&self) -> uint { self.nodes.len() }
+
+    /// `true` if no nodes were found
+    pub fn is_empty(&self) -> bool { self.nodes.is_empty() }
+
+    /// `true` if Cayley replied with `null` instead of the result, i.e. the start node is not
+    /// in the database at all, while an empty result means that nothing was found on the path
+    pub fn is_null(&self) -> bool { self.null }
+
+    /// The node at the given position, if there is one
+    pub fn get<'a>(&'a self, index: uint) -> Option<CompactNode<'a>> {
+        match self.nodes.as_slice().get(index) {
+            Some(&(start, end)) => Some(CompactNode { owner: self,
+                                                      tags: self.tags.slice(start, end) }),
+            None => None
+        }
+    }
+
+    /// Iterate over the nodes
+    pub fn iter<'a>(&'a self) -> CompactNodesIter<'a> {
+        CompactNodesIter { owner: self, position: 0 }
+    }
+
+    /// Every tag name met in the result
+    pub fn keys<'a>(&'a self) -> Vec<&'a str> {
+        self.keys.iter().map(|key| key.as_slice()).collect()
+    }
+
+    fn key_index(&self, key: &str) -> Option<uint> {
+        self.keys.iter().position(|known| known.as_slice() == key)
+    }
+
+    fn value<'a>(&'a self, start: uint, end: uint) -> &'a str {
+        self.buffer.as_slice().slice(start, end)
+    }
+
+}
+
+impl<'a> CompactNode<'a> {
+
+    /// Value of the tag, if the node has it
+    pub fn get(&self, key: &str) -> Option<&'a str> {
+        let index = match self.owner.key_index(key) {
+            Some(index) => index,
+            None => return None
+        };
+        match self.tags.iter().find(|&&(tag_key, _, _)| tag_key == index) {
+            Some(&(_, start, end)) => Some(self.owner.value(start, end)),
+            None => None
+        }
+    }
+
+    /// Number of tags in the node
+    pub fn len(&self) -> uint { self.tags.len() }
+
+    /// Iterate over `(tag, value)` pairs of the node
+    pub fn tags(&self) -> CompactTags<'a> {
+        CompactTags { owner: self.owner, tags: self.tags, position: 0 }
+    }
+
+    /// Copy the node into an owned [GraphNode](../graph/struct.GraphNode.html)
+    pub fn to_node(&self) -> GraphNode {
+        GraphNode(self.tags().map(|(key, value)| (key.to_string(), value.to_string())).collect())
+    }
+
+}
+
+impl<'a> Iterator<CompactNode<'a>> for CompactNodesIter<'a> {
+    fn next(&mut self) -> Option<CompactNode<'a>> {
+        let node = self.owner.get(self.position);
+        self.position += 1;
+        node
+    }
+}
+
+impl<'a> Iterator<(&'a str, &'a str)> for CompactTags<'a> {
+    fn next(&mut self) -> Option<(&'a str, &'a str)> {
+        match self.tags.get(self.position) {
+            Some(&(key, start, end)) => {
+                self.position += 1;
+                Some((self.owner.keys[key].as_slice(), self.owner.value(start, end)))
+            },
+            None => None
+        }
+    }
+}
+
+impl Builder {
+
+    fn new() -> Builder {
+        Builder { keys: HashMap::new(),
+                  nodes: CompactNodes { keys: Vec::new(), buffer: String::new(),
+                                        tags: Vec::new(), nodes: Vec::new(), null: false },
+                  node_start: None }
+    }
+
+    fn start_node(&mut self) {
+        self.node_start = Some(self.nodes.tags.len());
+    }
+
+    fn end_node(&mut self) {
+        match self.node_start.take() {
+            Some(start) => self.nodes.nodes.push((start, self.nodes.tags.len())),
+            None => ()
+        }
+    }
+
+    fn null_result(&mut self) {
+        self.nodes.null = true;
+    }
+
+    fn add_tag(&mut self, key: &str, value: &str) {
+        // the names are repeated in every node, so the known ones are found without a copy
+        let key_index = match self.keys.find_equiv(&key) {
+            Some(index) => *index,
+            None => {
+                let index = self.nodes.keys.len();
+                self.nodes.keys.push(key.to_string());
+                self.keys.insert(key.to_string(), index);
+                index
+            }
+        };
+        let start = self.nodes.buffer.len();
+        self.nodes.buffer.push_str(value);
+        self.nodes.tags.push((key_index, start, self.nodes.buffer.len()));
+    }
+
+    fn finish(self) -> CompactNodes {
+        let mut nodes = self.nodes;
+        nodes.buffer.shrink_to_fit();
+        nodes.tags.shrink_to_fit();
+        nodes.nodes.shrink_to_fit();
+        nodes
+    }
+
+}
@@ -0,0 +1,551 @@
+use std::io;
+use std::io::{IoResult, IoError, BufferedReader, EndOfFile, ConnectionReset, InvalidInput, TimedOut};
+use std::io::net::tcp::TcpStream;
+use std::io::net::ip::SocketAddr;
+use std::io::net::addrinfo::get_host_addresses;
+use std::time::Duration;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, SeqCst};
+use std::num;
+use std::i64;
+use std::cmp;
+
+use time;
+
+use url::Url;
+use http::client::{RequestWriter, ResponseReader, NetworkStream};
+use http::client::sslclients::{NormalStream, SslProtectedStream};
+use http::connecter::Connecter;
+use http::method::Post;
+use http::headers::content_type::MediaType;
+use http::headers::transfer_encoding::Chunked;
+use http::headers::connection::{Token, Close};
+
+use graph::GraphConfig;
+
+/// A pool of persistent connections, `Graph` sends every request through it
+///
+/// Requests are still written and responses are parsed by [rust-http](https://github.com/chris-morgan/rust-http),
+/// the pool only decides which connection the request goes through: it is taken from the pool,
+/// or a new one is opened, if there are no idle ones to the same host, and it is returned to
+/// the pool when the response is read completely, unless the server asked to close it. At most
+/// `max_idle` connections are kept. Both `http://` and `https://` connections are pooled.
+///
+/// Timeouts from [GraphConfig](../graph/struct.GraphConfig.html) are applied to every
+/// connection, every blocking operation fails with `TimedOut` error when they expire.
+pub struct ConnectionPool {
+    idle: Arc<Mutex<Vec<IdleStream>>>,
+    config: GraphConfig
+}
+
+/// A request prepared to be sent through the [pool](./struct.ConnectionPool.html)
+pub struct PooledRequest<'a> {
+    pool: &'a ConnectionPool,
+    writer: RequestWriter<PooledStream>
+}
+
+/// A response, which body is not read yet
+pub struct Response {
+    /// Status code, i.e. `200`
+    pub status: u16,
+    /// Body of the response, read it to the end to return the connection to the pool
+    pub body: ResponseBody
+}
+
+/// Body of the [Response](./struct.Response.html), the connection is returned to the pool
+/// as soon as the body is read to the end, and closed, if the body is dropped before
+pub struct ResponseBody {
+    reader: Option<BufferedReader<ResponseReader<PooledStream>>>,
+    framing: Framing,
+    keep_alive: bool,
+    state: Arc<ExchangeState>
+}
+
+/// A request which body is being sent with chunked transfer encoding, write the
+/// (already framed) chunks into it and call `finish()` to get the response
+pub struct ChunkedRequest {
+    writer: RequestWriter<PooledStream>,
+    state: Arc<ExchangeState>
+}
+
+/// The stream rust-http sends the requests through, it is a connection taken from the pool
+/// or a new one, and it goes back to the pool when the response is read to the end
+pub struct PooledStream {
+    stream: Option<NetworkStream>,
+    destination: Destination,
+    idle: Arc<Mutex<Vec<IdleStream>>>,
+    max_idle: uint,
+    // time allowed for a single read or write, in milliseconds
+    read_timeout: Option<u64>,
+    // the time the current request should be completed by, in nanoseconds, see `time::precise_time_ns`
+    deadline: Option<u64>,
+    state: Arc<ExchangeState>
+}
+
+// the host, the port and the scheme a connection is opened to, only the connections to
+// the same destination are re-used
+#[deriving(Clone, PartialEq)]
+struct Destination {
+    host: String,
+    port: u16,
+    secure: bool
+}
+
+struct IdleStream {
+    destination: Destination,
+    stream: NetworkStream
+}
+
+// what the pool and the stream know about the current request
+struct ExchangeState {
+    // some bytes of the response are received
+    received: AtomicBool,
+    // the response is read to the end, and the server allows to send the next request
+    reusable: AtomicBool
+}
+
+// everything `PooledStream::connect` needs, rust-http calls it with the address only,
+// so the pool leaves it in the task-local slot right before the call
+struct Checkout {
+    stream: Option<NetworkStream>,
+    destination: Destination,
+    idle: Arc<Mutex<Vec<IdleStream>>>,
+    config: GraphConfig,
+    deadline: Option<u64>,
+    state: Arc<ExchangeState>
+}
+
+local_data_key!(CHECKOUT: Checkout)
+
+// tells where the body of the response ends
+enum Framing {
+    // `Content-Length` bytes are left
+    Length(u64),
+    // `Transfer-Encoding: chunked`, the size of the next chunk is to be read
+    ChunkStart,
+    // `Transfer-Encoding: chunked`, bytes left in the current chunk
+    ChunkData(u64),
+    // no length is known, the body ends when the connection is closed
+    UntilClose,
+    // the body is read to the end
+    Done
+}
+
+impl ConnectionPool {
+
+    /// Create an empty pool, no connection is opened until the first request
+    pub fn new(config: GraphConfig) -> ConnectionPool {
+        ConnectionPool { idle: Arc::new(Mutex::new(Vec::new())), config: config }
+    }
+
+    /// Apply new settings to the pool, idle connections are closed, so the
+    /// new ones are opened with these settings
+    pub fn configure(&mut self, config: GraphConfig) {
+        self.clear();
+        self.config = config;
+    }
+
+    /// Prepare a `POST` request to the URL, the error tells that the URL can't be requested
+    /// at all (i.e. its host is not resolved), since no connection is used yet
+    pub fn prepare<'a>(&'a self, url: Url) -> IoResult<PooledRequest<'a>> {
+        let writer = try!(RequestWriter::new(Post, url));
+        Ok(PooledRequest { pool: self, writer: writer })
+    }
+
+    /// Close all the idle connections
+    pub fn clear(&self) {
+        self.idle.lock().clear();
+    }
+
+    // the time the request started now should be completed by
+    fn deadline(&self) -> Option<u64> {
+        self.config.deadline.map(|deadline| time::precise_time_ns() + nanoseconds(deadline))
+    }
+
+    fn take_idle(&self, destination: &Destination) -> Option<NetworkStream> {
+        let mut idle = self.idle.lock();
+        match idle.iter().rposition(|idle| idle.destination == *destination) {
+            Some(position) => idle.remove(position).map(|idle| idle.stream),
+            None => None
+        }
+    }
+
+    // hands the connection to `PooledStream::connect`, rust-http opens the stream lazily,
+    // so it is called right away to be sure the slot is used by this request
+    fn connect(&self, writer: &mut RequestWriter<PooledStream>, stream: Option<NetworkStream>,
+               deadline: Option<u64>, state: Arc<ExchangeState>) -> IoResult<()> {
+        writer.headers.connection = Some(vec!(Token("keep-alive".to_string())));
+        CHECKOUT.replace(Some(Checkout { stream: stream, destination: Destination::of(&writer.url),
+                                         idle: self.idle.clone(), config: self.config.clone(),
+                                         deadline: deadline, state: state }));
+        let connected = writer.try_connect();
+        CHECKOUT.replace(None);
+        connected
+    }
+
+    // sends the request and reads the head of the response; the failure tells if the request
+    // was written completely and the connection was closed before a byte of the response came
+    fn exchange(&self, mut writer: RequestWriter<PooledStream>, stream: Option<NetworkStream>,
+                deadline: Option<u64>, body: &[u8]) -> Result<Response, (IoError, bool)> {
+        let state = Arc::new(ExchangeState::new());
+        match self.connect(&mut writer, stream, deadline, state.clone()) {
+            Err(error) => return Err((error, false)),
+            Ok(_) => ()
+        }
+        match writer.write(body).and_then(|_| writer.flush()) {
+            Err(error) => return Err((error, false)),
+            Ok(_) => ()
+        }
+        match writer.read_response() {
+            Err((_, error)) => {
+                let closed = !state.received.load(SeqCst) &&
+                             (error.kind == EndOfFile || error.kind == ConnectionReset);
+                Err((error, closed))
+            },
+            Ok(reader) => Ok(Response::from_reader(reader, state))
+        }
+    }
+
+}
+
+impl<'a> PooledRequest<'a> {
+
+    /// Send the body of known length and read the head of the response
+    ///
+    /// The request goes through an idle connection, if there is one. If the server closed that
+    /// connection before, the response is cut before its first byte; then, with `resend`, the
+    /// request is sent once again through a new connection. Cayley could apply the request
+    /// before the connection was closed, so pass `true` only for the requests which do no harm
+    /// when they are repeated.
+    pub fn post(self, content_type: Option<MediaType>, body: &[u8], resend: bool)
+                -> IoResult<Response> {
+        let PooledRequest { pool, mut writer } = self;
+        let url = writer.url.clone();
+        let deadline = pool.deadline();
+        let stream = pool.take_idle(&Destination::of(&url));
+        let reused = stream.is_some();
+        writer.headers.content_type = content_type.clone();
+        writer.headers.content_length = Some(body.len());
+        let (error, closed) = match pool.exchange(writer, stream, deadline, body) {
+            Ok(response) => return Ok(response),
+            Err(failure) => failure
+        };
+        if !(reused && closed && resend) { return Err(error); }
+        let mut writer = try!(RequestWriter::new(Post, url));
+        writer.headers.content_type = content_type;
+        writer.headers.content_length = Some(body.len());
+        match pool.exchange(writer, None, deadline, body) {
+            Ok(response) => Ok(response),
+            Err((error, _)) => Err(error)
+        }
+    }
+
+    /// Start sending the body with chunked transfer encoding, it is always sent through
+    /// a new connection and never repeated
+    pub fn post_chunked(self, content_type: MediaType) -> IoResult<ChunkedRequest> {
+        let PooledRequest { pool, mut writer } = self;
+        writer.headers.content_type = Some(content_type);
+        writer.headers.transfer_encoding = Some(vec!(Chunked));
+        let state = Arc::new(ExchangeState::new());
+        try!(pool.connect(&mut writer, None, pool.deadline(), state.clone()));
+        Ok(ChunkedRequest { writer: writer, state: state })
+    }
+
+}
+
+impl ChunkedRequest {
+
+    /// Complete the request and read the head of the response, the last chunk
+    /// should already be written
+    pub fn finish(self) -> IoResult<Response> {
+        let ChunkedRequest { mut writer, state } = self;
+        try!(writer.flush());
+        match writer.read_response() {
+            Err((_, error)) => Err(error),
+            Ok(reader) => Ok(Response::from_reader(reader, state))
+        }
+    }
+
+}
+
+impl Writer for ChunkedRequest {
+    fn write(&mut self, data: &[u8]) -> IoResult<()> {
+        self.writer.write(data)
+    }
+
+    fn flush(&mut self) -> IoResult<()> {
+        self.writer.flush()
+    }
+}
+
+impl Response {
+
+    // takes the status and the framing of the body from the head rust-http has parsed
+    fn from_reader(reader: ResponseReader<PooledStream>, state: Arc<ExchangeState>) -> Response {
+        let status = reader.status.code();
+        let chunked = match reader.headers.transfer_encoding {
+            Some(ref codings) => codings.iter().any(|coding| *coding == Chunked),
+            None => false
+        };
+        let mut keep_alive = match reader.headers.connection {
+            Some(ref options) => !options.iter().any(|option| *option == Close),
+            None => reader.version == (1, 1)
+        };
+        let framing = match (status, chunked, reader.headers.content_length) {
+            (100...199, _, _) | (204, _, _) | (304, _, _) => Length(0),
+            (_, true, _) => ChunkStart,
+            (_, false, Some(length)) => Length(length as u64),
+            (_, false, None) => { keep_alive = false; UntilClose }
+        };
+        let mut body = ResponseBody { reader: Some(BufferedReader::new(reader)), framing: framing,
+                                      keep_alive: keep_alive, state: state };
+        body.complete_if_empty();
+        Response { status: status, body: body }
+    }
+
+}
+
+impl ResponseBody {
+
+    // the body of no length is completed right away, to return the connection
+    fn complete_if_empty(&mut self) {
+        match self.framing {
+            Length(0) => self.complete(),
+            _ => ()
+        }
+    }
+
+    // the body is read to the end, the connection may be used for the next request, it goes
+    // back to the pool when the stream is dropped
+    fn complete(&mut self) {
+        self.framing = Done;
+        self.state.reusable.store(self.keep_alive, SeqCst);
+        self.reader = None;
+    }
+
+    fn reader(&mut self) -> IoResult<&mut BufferedReader<ResponseReader<PooledStream>>> {
+        match self.reader {
+            Some(ref mut reader) => Ok(reader),
+            None => Err(io::standard_error(EndOfFile))
+        }
+    }
+
+    // reads the size of the next chunk, skipping the chunk extensions
+    fn read_chunk_size(&mut self) -> IoResult<u64> {
+        let line = try!(try!(self.reader()).read_line());
+        let size = line.as_slice().split(';').next().unwrap_or("").trim();
+        match num::from_str_radix::<u64>(size, 16) {
+            Some(size) => Ok(size),
+            None => Err(malformed(line.as_slice()))
+        }
+    }
+
+    // skips the trailer headers after the last chunk
+    fn read_trailers(&mut self) -> IoResult<()> {
+        loop {
+            let line = try!(try!(self.reader()).read_line());
+            if line.as_slice().trim_right_chars(['\r', '\n'].as_slice()).is_empty() {
+                return Ok(());
+            }
+        }
+    }
+
+}
+
+impl Reader for ResponseBody {
+    fn read(&mut self, buffer: &mut [u8]) -> IoResult<uint> {
+        if buffer.is_empty() { return Ok(0); }
+        loop {
+            match self.framing {
+                Done => return Err(io::standard_error(EndOfFile)),
+                Length(0) => self.complete(),
+                Length(left) => {
+                    let len = cmp::min(buffer.len() as u64, left) as uint;
+                    let read = try!(try!(self.reader()).read(buffer.slice_to_mut(len)));
+                    self.framing = Length(left - read as u64);
+                    return Ok(read);
+                },
+                ChunkStart => match try!(self.read_chunk_size()) {
+                    0 => { try!(self.read_trailers()); self.complete() },
+                    size => self.framing = ChunkData(size)
+                },
+                ChunkData(left) => {
+                    let len = cmp::min(buffer.len() as u64, left) as uint;
+                    let read = try!(try!(self.reader()).read(buffer.slice_to_mut(len)));
+                    if read as u64 == left {
+                        // the chunk data is followed by CRLF
+                        try!(try!(self.reader()).read_line());
+                        self.framing = ChunkStart;
+                    } else {
+                        self.framing = ChunkData(left - read as u64);
+                    }
+                    return Ok(read);
+                },
+                UntilClose => {
+                    let result = try!(self.reader()).read(buffer);
+                    match result {
+                        Err(ref error) if error.kind == EndOfFile => {
+                            self.framing = Done;
+                            self.reader = None;
+                        },
+                        result => return result
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl PooledStream {
+
+    // sets the timeouts for the next blocking operation and returns the stream, the socket
+    // timeouts are the deadlines, so they are updated before every operation
+    fn armed(&mut self) -> IoResult<&mut NetworkStream> {
+        let left = time_left(self.deadline).map(|left| left / 1000000);
+        let timeout = match (self.read_timeout, left) {
+            (_, Some(0)) => return Err(io::standard_error(TimedOut)),
+            (None, None) => None,
+            (Some(timeout), None) => Some(timeout),
+            (None, Some(left)) => Some(left),
+            (Some(timeout), Some(left)) => Some(cmp::min(timeout, left))
+        };
+        match self.stream {
+            Some(ref mut stream) => {
+                if timeout.is_some() { socket(stream).set_timeout(timeout); }
+                Ok(stream)
+            },
+            None => Err(io::standard_error(EndOfFile))
+        }
+    }
+
+}
+
+impl Connecter for PooledStream {
+    fn connect(addr: SocketAddr, host: &str, use_ssl: bool) -> IoResult<PooledStream> {
+        match CHECKOUT.replace(None) {
+            Some(checkout) => checkout.open(addr, host, use_ssl),
+            None => Err(IoError { kind: InvalidInput, desc: "the request is not sent through a pool",
+                                  detail: None })
+        }
+    }
+}
+
+impl Reader for PooledStream {
+    fn read(&mut self, buffer: &mut [u8]) -> IoResult<uint> {
+        let read = try!(try!(self.armed()).read(buffer));
+        if read > 0 { self.state.received.store(true, SeqCst); }
+        Ok(read)
+    }
+}
+
+impl Writer for PooledStream {
+    fn write(&mut self, data: &[u8]) -> IoResult<()> {
+        try!(self.armed()).write(data)
+    }
+
+    fn flush(&mut self) -> IoResult<()> {
+        try!(self.armed()).flush()
+    }
+}
+
+impl Drop for PooledStream {
+    fn drop(&mut self) {
+        if !self.state.reusable.load(SeqCst) { return; }
+        match self.stream.take() {
+            Some(stream) => {
+                let mut idle = self.idle.lock();
+                if idle.len() < self.max_idle {
+                    idle.push(IdleStream { destination: self.destination.clone(), stream: stream });
+                }
+            },
+            None => ()
+        }
+    }
+}
+
+impl Checkout {
+
+    // uses the idle connection, or opens a new one; plain connections are opened here, to apply
+    // the connect timeout, and the encrypted ones are opened by rust-http itself
+    fn open(self, addr: SocketAddr, host: &str, use_ssl: bool) -> IoResult<PooledStream> {
+        let Checkout { stream, destination, idle, config, deadline, state } = self;
+        let stream = match stream {
+            Some(stream) => stream,
+            None if use_ssl => {
+                let secure: NetworkStream = try!(Connecter::connect(addr, host, true));
+                secure
+            },
+            None => NormalStream(try!(connect_tcp(host, addr.port, config.connect_timeout, deadline)))
+        };
+        Ok(PooledStream { stream: Some(stream), destination: destination, idle: idle,
+                          max_idle: config.max_idle,
+                          read_timeout: config.read_timeout
+                                              .map(|timeout| timeout.num_milliseconds() as u64),
+                          deadline: deadline, state: state })
+    }
+
+}
+
+impl Destination {
+    fn of(url: &Url) -> Destination {
+        Destination { host: url.serialize_host().unwrap_or(String::new()),
+                      port: url.port_or_default().unwrap_or(0),
+                      secure: url.scheme.as_slice() == "https" }
+    }
+}
+
+impl ExchangeState {
+    fn new() -> ExchangeState {
+        ExchangeState { received: AtomicBool::new(false), reusable: AtomicBool::new(false) }
+    }
+}
+
+// tries every address of the host in turn, until one of them accepts the connection
+fn connect_tcp(host: &str, port: u16, connect_timeout: Option<Duration>, deadline: Option<u64>)
+               -> IoResult<TcpStream> {
+    let addresses = try!(get_host_addresses(host));
+    let mut last_error = IoError { kind: InvalidInput, desc: "host is not found",
+                                   detail: Some(host.to_string()) };
+    for ip in addresses.into_iter() {
+        let timeout = match (connect_timeout, time_left(deadline)) {
+            (_, Some(0)) => return Err(io::standard_error(TimedOut)),
+            (None, None) => None,
+            (Some(timeout), None) => Some(timeout),
+            (None, Some(left)) => Some(Duration::nanoseconds(left as i64)),
+            (Some(timeout), Some(left)) => Some(cmp::min(timeout, Duration::nanoseconds(left as i64)))
+        };
+        let connected = match timeout {
+            None => TcpStream::connect(ip.to_string().as_slice(), port),
+            Some(timeout) => TcpStream::connect_timeout(SocketAddr { ip: ip, port: port }, timeout)
+        };
+        match connected {
+            Ok(stream) => return Ok(stream),
+            Err(error) => last_error = error
+        }
+    }
+    Err(last_error)
+}
+
+// the TCP connection under the stream, to set the timeouts
+fn socket(stream: &mut NetworkStream) -> &mut TcpStream {
+    match *stream {
+        NormalStream(ref mut tcp) => tcp,
+        SslProtectedStream(ref mut ssl) => ssl.get_mut()
+    }
+}
+
+fn nanoseconds(duration: Duration) -> u64 {
+    cmp::max(duration.num_nanoseconds().unwrap_or(i64::MAX), 0) as u64
+}
+
+// nanoseconds left till the deadline, if there is one
+fn time_left(deadline: Option<u64>) -> Option<u64> {
+    deadline.map(|deadline| {
+        let now = time::precise_time_ns();
+        if deadline > now { deadline - now } else { 0 }
+    })
+}
+
+fn malformed(line: &str) -> IoError {
+    IoError { kind: InvalidInput, desc: "malformed HTTP response", detail: Some(line.to_string()) }
+}
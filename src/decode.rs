@@ -0,0 +1,284 @@
+use std::mem;
+use std::from_str::FromStr;
+use std::error::Error;
+use std::fmt::{Show, Formatter, FormatError};
+
+use std::collections::HashMap;
+
+use serialize::{Decoder, Decodable};
+
+use graph::GraphNode;
+
+/// Decodes a [GraphNode](../graph/struct.GraphNode.html) into any `Decodable` structure,
+/// every field of the structure is taken from the tag with the same name (`id`, or any tag
+/// assigned with `.As(...)`/`.Save(...)`).
+///
+/// Tag values are strings in Cayley responses, so numeric, boolean and char fields are parsed
+/// from them. `Option` fields are `None` when there is no such tag in the node. The node may
+/// also be decoded into a map, i.e. `HashMap<String, int>`, if every tag has the same type.
+///
+/// ```
+/// extern crate serialize;
+/// extern crate cayley;
+///
+/// use std::collections::HashMap;
+/// use cayley::GraphNode;
+/// use cayley::decode;
+///
+/// #[deriving(Decodable)]
+/// struct Film { id: String, year: int, sequel: Option<String> }
+///
+/// fn main() {
+///     let mut tags = HashMap::new();
+///     tags.insert("id".to_string(), "Casablanca".to_string());
+///     tags.insert("year".to_string(), "1942".to_string());
+///     let film: Film = decode::from_node(GraphNode(tags)).unwrap();
+///     assert_eq!(film.year, 1942);
+/// }
+/// ```
+pub struct NodeDecoder {
+    tags: HashMap<String, String>,
+    // name of the tag which is decoded at the moment, if there is one
+    tag: Option<String>,
+    // value of this tag, taken by the first read of a primitive
+    value: Option<String>,
+    // tags of the node, when it is decoded as a map
+    entries: Vec<(String, String)>
+}
+
+/// A reason the node failed to be decoded with the [NodeDecoder](./struct.NodeDecoder.html)
+#[deriving(Clone, PartialEq)]
+pub enum NodeDecoderError {
+    /// There is no tag for the required field, holds the name of the tag
+    MissingTag(String),
+    /// The tag value can not be converted to the type of the field,
+    /// holds the name of the tag, its value and the expected type
+    InvalidValue(String, String, String),
+    /// The field type is neither primitive nor optional, i.e. a list or a nested structure,
+    /// holds the name of the tag
+    UnsupportedType(String),
+    /// The error reported by the `Decodable` implementation itself
+    CustomError(String)
+}
+
+pub type NodeDecoderResult<T> = Result<T, NodeDecoderError>;
+
+/// Decode a node into a structure, see [NodeDecoder](./struct.NodeDecoder.html)
+pub fn from_node<T: Decodable<NodeDecoder, NodeDecoderError>>(node: GraphNode)
+                                                             -> NodeDecoderResult<T> {
+    let mut decoder = NodeDecoder::new(node);
+    Decodable::decode(&mut decoder)
+}
+
+impl NodeDecoder {
+
+    /// Create a decoder which takes field values from the tags of the node
+    pub fn new(node: GraphNode) -> NodeDecoder {
+        let GraphNode(tags) = node;
+        NodeDecoder { tags: tags, tag: None, value: None, entries: Vec::new() }
+    }
+
+    // name of the current tag, for error messages
+    fn tag_name(&self) -> String {
+        match self.tag {
+            Some(ref tag) => tag.clone(),
+            None => String::new()
+        }
+    }
+
+    // take the value of the current tag as is
+    fn take_value(&mut self) -> NodeDecoderResult<String> {
+        match self.value.take() {
+            Some(value) => Ok(value),
+            None => Err(MissingTag(self.tag_name()))
+        }
+    }
+
+    // take the value of the current tag and convert it to the type of the field
+    fn parse_value<T: FromStr>(&mut self, expected: &str) -> NodeDecoderResult<T> {
+        let value = try!(self.take_value());
+        match from_str::<T>(value.as_slice()) {
+            Some(parsed) => Ok(parsed),
+            None => Err(InvalidValue(self.tag_name(), value, expected.to_string()))
+        }
+    }
+
+    fn unsupported<T>(&self) -> NodeDecoderResult<T> {
+        Err(UnsupportedType(self.tag_name()))
+    }
+
+}
+
+impl Decoder<NodeDecoderError> for NodeDecoder {
+
+    // ----- primitives -----
+
+    fn read_nil(&mut self) -> NodeDecoderResult<()> { Ok(()) }
+
+    fn read_uint(&mut self) -> NodeDecoderResult<uint> { self.parse_value("uint") }
+    fn read_u64(&mut self) -> NodeDecoderResult<u64> { self.parse_value("u64") }
+    fn read_u32(&mut self) -> NodeDecoderResult<u32> { self.parse_value("u32") }
+    fn read_u16(&mut self) -> NodeDecoderResult<u16> { self.parse_value("u16") }
+    fn read_u8(&mut self) -> NodeDecoderResult<u8> { self.parse_value("u8") }
+
+    fn read_int(&mut self) -> NodeDecoderResult<int> { self.parse_value("int") }
+    fn read_i64(&mut self) -> NodeDecoderResult<i64> { self.parse_value("i64") }
+    fn read_i32(&mut self) -> NodeDecoderResult<i32> { self.parse_value("i32") }
+    fn read_i16(&mut self) -> NodeDecoderResult<i16> { self.parse_value("i16") }
+    fn read_i8(&mut self) -> NodeDecoderResult<i8> { self.parse_value("i8") }
+
+    fn read_bool(&mut self) -> NodeDecoderResult<bool> { self.parse_value("bool") }
+
+    fn read_f64(&mut self) -> NodeDecoderResult<f64> { self.parse_value("f64") }
+    fn read_f32(&mut self) -> NodeDecoderResult<f32> { self.parse_value("f32") }
+
+    fn read_char(&mut self) -> NodeDecoderResult<char> {
+        let value = try!(self.take_value());
+        match value.as_slice().chars().collect::<Vec<char>>().as_slice() {
+            [c] => Ok(c),
+            _ => Err(InvalidValue(self.tag_name(), value.clone(), "char".to_string()))
+        }
+    }
+
+    fn read_str(&mut self) -> NodeDecoderResult<String> { self.take_value() }
+
+    // ----- structure -----
+
+    fn read_struct<T>(&mut self, _name: &str, _len: uint,
+                      f: |&mut NodeDecoder| -> NodeDecoderResult<T>) -> NodeDecoderResult<T> {
+        match self.tag {
+            Some(_) => self.unsupported(),
+            None => f(self)
+        }
+    }
+
+    fn read_struct_field<T>(&mut self, name: &str, _idx: uint,
+                            f: |&mut NodeDecoder| -> NodeDecoderResult<T>) -> NodeDecoderResult<T> {
+        self.value = self.tags.pop(&name.to_string());
+        self.tag = Some(name.to_string());
+        let result = f(self);
+        self.tag = None;
+        self.value = None;
+        result
+    }
+
+    fn read_option<T>(&mut self, f: |&mut NodeDecoder, bool| -> NodeDecoderResult<T>)
+                      -> NodeDecoderResult<T> {
+        let has_value = self.value.is_some();
+        f(self, has_value)
+    }
+
+    // ----- map -----
+
+    fn read_map<T>(&mut self, f: |&mut NodeDecoder, uint| -> NodeDecoderResult<T>)
+                   -> NodeDecoderResult<T> {
+        match self.tag {
+            Some(_) => self.unsupported(),
+            None => {
+                let tags = mem::replace(&mut self.tags, HashMap::new());
+                self.entries = tags.into_iter().collect();
+                let len = self.entries.len();
+                f(self, len)
+            }
+        }
+    }
+
+    fn read_map_elt_key<T>(&mut self, idx: uint, f: |&mut NodeDecoder| -> NodeDecoderResult<T>)
+                           -> NodeDecoderResult<T> {
+        let (key, _) = self.entries[idx].clone();
+        self.tag = Some(key.clone());
+        self.value = Some(key);
+        f(self)
+    }
+
+    fn read_map_elt_val<T>(&mut self, idx: uint, f: |&mut NodeDecoder| -> NodeDecoderResult<T>)
+                           -> NodeDecoderResult<T> {
+        let (key, value) = self.entries[idx].clone();
+        self.tag = Some(key);
+        self.value = Some(value);
+        let result = f(self);
+        self.tag = None;
+        self.value = None;
+        result
+    }
+
+    // ----- unsupported -----
+
+    fn read_enum<T>(&mut self, _name: &str, _f: |&mut NodeDecoder| -> NodeDecoderResult<T>)
+                    -> NodeDecoderResult<T> { self.unsupported() }
+
+    fn read_enum_variant<T>(&mut self, _names: &[&str],
+                            _f: |&mut NodeDecoder, uint| -> NodeDecoderResult<T>)
+                            -> NodeDecoderResult<T> { self.unsupported() }
+
+    fn read_enum_variant_arg<T>(&mut self, _idx: uint,
+                                _f: |&mut NodeDecoder| -> NodeDecoderResult<T>)
+                                -> NodeDecoderResult<T> { self.unsupported() }
+
+    fn read_enum_struct_variant<T>(&mut self, _names: &[&str],
+                                   _f: |&mut NodeDecoder, uint| -> NodeDecoderResult<T>)
+                                   -> NodeDecoderResult<T> { self.unsupported() }
+
+    fn read_enum_struct_variant_field<T>(&mut self, _name: &str, _idx: uint,
+                                         _f: |&mut NodeDecoder| -> NodeDecoderResult<T>)
+                                         -> NodeDecoderResult<T> { self.unsupported() }
+
+    fn read_tuple<T>(&mut self, _f: |&mut NodeDecoder, uint| -> NodeDecoderResult<T>)
+                     -> NodeDecoderResult<T> { self.unsupported() }
+
+    fn read_tuple_arg<T>(&mut self, _idx: uint, _f: |&mut NodeDecoder| -> NodeDecoderResult<T>)
+                         -> NodeDecoderResult<T> { self.unsupported() }
+
+    fn read_tuple_struct<T>(&mut self, _name: &str,
+                            _f: |&mut NodeDecoder, uint| -> NodeDecoderResult<T>)
+                            -> NodeDecoderResult<T> { self.unsupported() }
+
+    fn read_tuple_struct_arg<T>(&mut self, _idx: uint,
+                                _f: |&mut NodeDecoder| -> NodeDecoderResult<T>)
+                                -> NodeDecoderResult<T> { self.unsupported() }
+
+    fn read_seq<T>(&mut self, _f: |&mut NodeDecoder, uint| -> NodeDecoderResult<T>)
+                   -> NodeDecoderResult<T> { self.unsupported() }
+
+    fn read_seq_elt<T>(&mut self, _idx: uint, _f: |&mut NodeDecoder| -> NodeDecoderResult<T>)
+                       -> NodeDecoderResult<T> { self.unsupported() }
+
+    fn error(&mut self, message: &str) -> NodeDecoderError {
+        CustomError(message.to_string())
+    }
+
+}
+
+impl Show for NodeDecoderError {
+
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), FormatError> {
+        match *self {
+            MissingTag(ref tag) =>
+                write!(fmt, "Tag \"{:s}\" is missing in the node", tag.as_slice()),
+            InvalidValue(ref tag, ref value, ref expected) =>
+                write!(fmt, "Tag \"{:s}\" has the value \"{:s}\" which is not a valid {:s}",
+                       tag.as_slice(), value.as_slice(), expected.as_slice()),
+            UnsupportedType(ref tag) =>
+                write!(fmt, "Tag \"{:s}\" can not be decoded into a list or a nested structure",
+                       tag.as_slice()),
+            CustomError(ref message) => fmt.pad(message.as_slice())
+        }
+    }
+}
+
+impl Error for NodeDecoderError {
+
+    fn description(&self) -> &str {
+        match *self {
+            MissingTag(..) => "missing tag",
+            InvalidValue(..) => "invalid tag value",
+            UnsupportedType(..) => "unsupported field type",
+            CustomError(..) => "node decoding failed"
+        }
+    }
+
+    fn detail(&self) -> Option<String> {
+        Some(self.to_string())
+    }
+
+}
@@ -1,16 +1,69 @@
 use url::ParseError;
-use std::io::IoError;
+use std::io::{IoError, TimedOut as IoTimedOut};
+use std::error::Error;
 use std::fmt::{Show, Formatter, FormatError};
 use serialize::json::DecoderError;
 
+use step::Step;
+use decode::NodeDecoderError;
+
 pub enum GraphRequestError {
     InvalidUrl(ParseError, String),
     MalformedRequest(IoError, String),
     RequestFailed(IoError, String),
+    ReadFailed(IoError, String),
     DecodingFailed(DecoderError, String),
     ResponseParseFailed,
-    QueryNotFinalized,
-    QueryCompilationFailed
+    QueryBuildFailed(Vec<BuildError>),
+    NodeDecodingFailed(NodeDecoderError),
+    ServerError(u16, String, String),
+    Timeout(String),
+    RetriesExhausted(Box<GraphRequestError>, uint),
+    CircuitOpen(String),
+    NotSupported(String)
+}
+
+/// A stable classification of [GraphRequestError](./enum.GraphRequestError.html), returned from
+/// its `kind()` method, to handle the failures without looking into their details
+#[deriving(Clone, PartialEq, Show)]
+pub enum ErrorKind {
+    /// The connection to Cayley failed, or was broken while the request was sent or the
+    /// response was read
+    NetworkError,
+    /// The connection or the request took longer than allowed
+    TimedOut,
+    /// The request was not sent, since the URL, the query or the uploaded source are invalid,
+    /// or the API version of the graph does not support such requests
+    InvalidRequest,
+    /// Cayley rejected the request
+    RejectedByServer,
+    /// Cayley replied with the response which can not be read
+    InvalidResponse,
+    /// The request was not sent, since Cayley failed too many times in a row, see
+    /// [CircuitBreaker](../breaker/struct.CircuitBreaker.html)
+    Unavailable
+}
+
+/// A mistake made while building a path with raw steps (see `Compile::add_step`), recorded
+/// by the path itself and reported when the path is compiled. Every error holds the step
+/// which was refused, or the name, if it was the name which was refused.
+#[deriving(Clone, PartialEq)]
+pub enum BuildError {
+    /// The path has no steps at all, not even the start one
+    EmptyPath,
+    /// The step was added before the path was started
+    NotStarted(Step),
+    /// The start step was added to the path which is already started
+    StartedTwice(Step),
+    /// The finalizer was called on the query which is already finalized
+    FinalizedTwice(Step),
+    /// The step was added after the query was finalized
+    StepAfterFinalizer(Step),
+    /// The query or the morphism passed to the step has errors itself
+    SubQueryFailed(Step, Vec<BuildError>),
+    /// The name of the morphism is not a JavaScript identifier, so the morphism can't be declared
+    /// as a variable in the query
+    InvalidName(String)
 }
 
 impl Show for GraphRequestError {
@@ -27,14 +80,151 @@ impl Show for GraphRequestError {
             RequestFailed(ref ioerr, ref path) => {
                 write!(fmt, "Path(\"{}\"): ", path.as_slice());
                 ioerr.fmt(fmt) },
+            ReadFailed(ref ioerr, ref source) => {
+                write!(fmt, "Source(\"{}\"): ", source.as_slice());
+                ioerr.fmt(fmt) },
             DecodingFailed(ref derr, ref src) => {
                 write!(fmt, "Source(\"{}\"): ", src.as_slice());
                 derr.fmt(fmt) },
             ResponseParseFailed => fmt.pad("Response parsing failed"),
-            QueryNotFinalized => fmt.pad("Query is not finalized"),
-            QueryCompilationFailed => fmt.pad("Query can not be compiled")
+            QueryBuildFailed(ref errors) => {
+                write!(fmt, "Query is built with errors: ");
+                write_errors(fmt, errors) },
+            NodeDecodingFailed(ref derr) => {
+                write!(fmt, "Node decoding failed: ");
+                derr.fmt(fmt) },
+            ServerError(status, ref message, ref query) =>
+                write!(fmt, "Query(\"{}\"): Server replied with {}: {}",
+                       query.as_slice(), status, message.as_slice()),
+            Timeout(ref source) =>
+                write!(fmt, "Source(\"{}\"): Timed out", source.as_slice()),
+            RetriesExhausted(ref error, attempts) => {
+                write!(fmt, "Failed after {} attempts: ", attempts);
+                error.fmt(fmt) },
+            CircuitOpen(ref url) =>
+                write!(fmt, "Url(\"{}\"): Circuit is open, Cayley is not requested", url.as_slice()),
+            NotSupported(ref request) =>
+                write!(fmt, "{} is not supported by the API version of the graph", request.as_slice())
+        }
+    }
+}
+
+impl GraphRequestError {
+
+    /// The kind of the failure, see [ErrorKind](./enum.ErrorKind.html)
+    pub fn kind(&self) -> ErrorKind {
+        match *self {
+            RetriesExhausted(ref error, _) => error.kind(),
+            Timeout(..) => TimedOut,
+            RequestFailed(ref ioerr, _) if ioerr.kind == IoTimedOut => TimedOut,
+            RequestFailed(..) => NetworkError,
+            InvalidUrl(..) | MalformedRequest(..) | ReadFailed(..) | QueryBuildFailed(..)
+                | NotSupported(..) => InvalidRequest,
+            ServerError(..) => RejectedByServer,
+            DecodingFailed(..) | ResponseParseFailed | NodeDecodingFailed(..) => InvalidResponse,
+            CircuitOpen(..) => Unavailable
+        }
+    }
+
+    /// `true` if the same request may succeed when it is sent once again: the connection
+    /// failed or timed out, or the server failed itself (replied with `5xx` status)
+    pub fn is_retryable(&self) -> bool {
+        match (self.kind(), self) {
+            (_, &RetriesExhausted(ref error, _)) => error.is_retryable(),
+            (NetworkError, _) | (TimedOut, _) => true,
+            (RejectedByServer, &ServerError(status, _, _)) => status >= 500,
+            (_, _) => false
+        }
+    }
+
+    /// Number of attempts made to send the request, more than `1` only if it was repeated
+    /// with a [RetryPolicy](../retry/struct.RetryPolicy.html)
+    pub fn attempts(&self) -> uint {
+        match *self {
+            RetriesExhausted(_, attempts) => attempts,
+            _ => 1
+        }
+    }
+
+}
+
+impl Error for GraphRequestError {
+
+    fn description(&self) -> &str {
+        match *self {
+            InvalidUrl(..) => "invalid url",
+            MalformedRequest(..) => "malformed request",
+            RequestFailed(..) => "request failed",
+            ReadFailed(..) => "source read failed",
+            DecodingFailed(..) => "response decoding failed",
+            ResponseParseFailed => "response parsing failed",
+            QueryBuildFailed(..) => "query is built with errors",
+            NodeDecodingFailed(..) => "node decoding failed",
+            ServerError(..) => "server rejected the request",
+            Timeout(..) => "request timed out",
+            RetriesExhausted(..) => "request failed after retries",
+            CircuitOpen(..) => "circuit is open",
+            NotSupported(..) => "request is not supported"
+        }
+    }
+
+    fn detail(&self) -> Option<String> {
+        Some(self.to_string())
+    }
+
+    fn cause(&self) -> Option<&Error> {
+        match *self {
+            MalformedRequest(ref ioerr, _) | RequestFailed(ref ioerr, _) | ReadFailed(ref ioerr, _) =>
+                Some(ioerr as &Error),
+            DecodingFailed(ref derr, _) => Some(derr as &Error),
+            NodeDecodingFailed(ref derr) => Some(derr as &Error),
+            RetriesExhausted(ref error, _) => Some(&**error as &Error),
+            _ => None
+        }
+    }
+
+}
+
+impl Show for BuildError {
+
+    #[allow(unused_must_use)]
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), FormatError> {
+        match *self {
+            EmptyPath => fmt.pad("Path is not started"),
+            NotStarted(ref step) =>
+                write!(fmt, "`.{:s}` is called before the path is started", step.name()),
+            StartedTwice(ref step) =>
+                write!(fmt, "`.{:s}` is called on the path which is already started", step.name()),
+            FinalizedTwice(ref step) =>
+                write!(fmt, "`.{:s}` is called on the query which is already finalized", step.name()),
+            StepAfterFinalizer(ref step) =>
+                write!(fmt, "`.{:s}` is called after the query is finalized", step.name()),
+            SubQueryFailed(ref step, ref errors) => {
+                write!(fmt, "`.{:s}` is called with the invalid query: ", step.name());
+                write_errors(fmt, errors) },
+            InvalidName(ref name) =>
+                write!(fmt, "`{:s}` is not a valid JavaScript identifier", name.as_slice())
         }
     }
 }
 
+#[allow(unused_must_use)]
+fn write_errors(fmt: &mut Formatter, errors: &Vec<BuildError>) -> Result<(), FormatError> {
+    write!(fmt, "[");
+    for (index, error) in errors.iter().enumerate() {
+        if index > 0 { write!(fmt, "; "); }
+        error.fmt(fmt);
+    }
+    write!(fmt, "]")
+}
+
+/// `RequestFailed` with the I/O error, or `Timeout`, if the error tells that the
+/// time allowed for the request is out
+pub fn request_failed(error: IoError, source: String) -> GraphRequestError {
+    match error.kind {
+        IoTimedOut => Timeout(source),
+        _ => RequestFailed(error, source)
+    }
+}
+
 pub type GraphResult<T> = Result<T, GraphRequestError>;
@@ -0,0 +1,90 @@
+use serialize::json;
+
+/// A piece of the `result` field of a Cayley response
+pub enum ResultEvent {
+    /// An object started, every object of the result is a node
+    NodeStart,
+    /// A tag of the node which is being read, its name and its value as a string
+    NodeTag(String, String),
+    /// The node is read completely
+    NodeEnd,
+    /// A bare value in the list, like the ones `.ToArray()` returns, the id of a node
+    NodeId(String),
+    /// The `result` itself is `null`, Cayley replies so when the start node is not in the database
+    NullResult
+}
+
+/// A reason the `result` field failed to be read
+pub enum ResultError {
+    /// The response is not a valid JSON
+    SyntaxError(json::ParserError),
+    /// The node has a list or an object inside, which can't be a tag value
+    NestedValue
+}
+
+/// Reads the response with the JSON parser and reports the nodes found in the `result`
+/// field, at any depth, while the values outside of it are skipped. Nothing but the node
+/// which is read at the moment is stored.
+pub struct ResultEvents<T> {
+    parser: json::Parser<T>,
+    in_node: bool,
+    failed: bool
+}
+
+impl<T: Iterator<char>> ResultEvents<T> {
+
+    pub fn new(source: T) -> ResultEvents<T> {
+        ResultEvents { parser: json::Parser::new(source), in_node: false, failed: false }
+    }
+
+    // a value inside a node is a tag, and a value in a list is an id of a node
+    fn value_event(&self, value: String) -> Option<ResultEvent> {
+        match (self.parser.stack().top(), self.in_node) {
+            (Some(json::Key(key)), true) => Some(NodeTag(key.to_string(), value)),
+            (Some(json::Index(_)), false) => Some(NodeId(value)),
+            (_, _) => None
+        }
+    }
+
+}
+
+impl<T: Iterator<char>> Iterator<Result<ResultEvent, ResultError>> for ResultEvents<T> {
+    fn next(&mut self) -> Option<Result<ResultEvent, ResultError>> {
+        if self.failed { return None; }
+        loop {
+            let event = match self.parser.next() {
+                Some(event) => event,
+                None => return None
+            };
+            let (in_result, at_result) = {
+                let stack = self.parser.stack();
+                let in_result = !stack.is_empty() && stack.get(0) == json::Key("result");
+                (in_result, in_result && stack.len() == 1)
+            };
+            let found = match (event, in_result, self.in_node) {
+                (json::Error(error), _, _) => {
+                    self.failed = true;
+                    return Some(Err(SyntaxError(error)));
+                },
+                (_, false, _) => None,
+                (json::ObjectStart, true, false) => { self.in_node = true; Some(NodeStart) },
+                (json::ObjectEnd, true, true) => { self.in_node = false; Some(NodeEnd) },
+                (json::ObjectStart, true, true) | (json::ListStart, true, true) => {
+                    self.failed = true;
+                    return Some(Err(NestedValue));
+                },
+                (json::StringValue(value), true, _) => self.value_event(value),
+                (json::I64Value(value), true, _) => self.value_event(value.to_string()),
+                (json::U64Value(value), true, _) => self.value_event(value.to_string()),
+                (json::F64Value(value), true, _) => self.value_event(value.to_string()),
+                (json::BooleanValue(value), true, _) => self.value_event(value.to_string()),
+                (json::NullValue, true, false) if at_result => Some(NullResult),
+                (_, true, _) => None
+            };
+            match found {
+                Some(event) => return Some(Ok(event)),
+                None => continue
+            }
+        }
+    }
+}
@@ -1,37 +1,110 @@
 use std::str;
+use std::cmp;
+use std::time::Duration;
+use std::default::Default;
 
+use std::io::{File, BufferedReader};
+
+use url;
 use url::Url;
-use http::client::RequestWriter;
-use http::method::Post;
+use http::headers::content_type::MediaType;
 
-use serialize::{Decoder, Decodable};
+use serialize::{Decoder, Decodable, Encoder, Encodable};
+use serialize::json;
 use serialize::json::decode as json_decode;
 
 use std::collections::HashMap;
 
-use path::Query;
+use path::FinalizedQuery;
+use step::{Finalizer, All, GetLimit, ToArray, ToValue, TagArray, TagValue, ForEach};
+
+use decode;
+use decode::{NodeDecoder, NodeDecoderError};
+
+use compact::CompactNodes;
+use stream::GraphNodesIter;
+use shape::Shape;
+use mql::Mql;
+
+use connection::{ConnectionPool, PooledRequest, Response, ResponseBody};
+use retry::RetryPolicy;
+use breaker::CircuitBreaker;
+
+use upload;
+use upload::{UploadProgress, SourceFailed, TargetFailed};
 
 use errors::{ GraphResult,
-              InvalidUrl, MalformedRequest, RequestFailed,
-              DecodingFailed, ResponseParseFailed,
-              QueryNotFinalized, QueryCompilationFailed };
+              InvalidUrl, MalformedRequest, ReadFailed,
+              DecodingFailed, ResponseParseFailed, NodeDecodingFailed, ServerError,
+              NotSupported };
+use errors::{GraphRequestError, request_failed};
 
 /// Provides access to currently running Cayley database, among with
-/// an ability to run queries there, and to write there your data
-/// (honestly, only if there's a `graph.emit()` method below—if not,
-/// it will just soon be there).
+/// an ability to run queries there, and to write there your data.
 ///
 /// * Use `Graph::default()` to connect to `localhost:64210`.
 /// * Use `Graph::new(host, port, api_version)` to specify the location of database manually.
+/// * Use `Graph::from_url(url, api_version)` to specify the location of database with URL, it also
+/// may contain a path prefix, if Cayley is mounted behind a reverse proxy.
+/// * Use `graph.with_config(<GraphConfig>)` to set the timeouts of the requests.
+/// * Use `graph.with_retry(<RetryPolicy>)` to change how the failed reads are repeated.
+/// * Use `graph.with_breaker(<CircuitBreaker>)` to fail fast while Cayley is down.
 ///
-/// * Use `Graph::find(<Query>)` to find anything using [Query](../path/trait.Query.html) trait implementor
-/// (`Query`, for example, is implemented by [Vertex](../path/struct.Vertex.html)), which in its turn
-/// is similar to [Gremlin API](https://github.com/google/cayley/blob/master/docs/GremlinAPI.md).
-/// * Use `Graph::find_by(<String>)` to find anything using [Gremlin API](https://github.com/google/cayley/blob/master/docs/GremlinAPI.md) API
+/// * Use `Graph::find(<FinalizedQuery>)` to find anything using [Query](../path/trait.Query.html) trait implementor
+/// (`Query`, for example, is implemented by [Vertex](../path/struct.Vertex.html)) finalized with `.All()`
+/// or any other finalizer, which in its turn is similar to [Gremlin API](https://github.com/google/cayley/blob/master/docs/GremlinAPI.md).
+/// * Use `Graph::find_as(<FinalizedQuery>)` to find the same and decode every node found into
+/// your own `Decodable` structure, which fields are named after the tags.
+/// * Use `Graph::find_compact(<FinalizedQuery>)` to find the same and store the nodes in a compact way,
+/// which is much cheaper for the large results.
+/// * Use `Graph::find_iter(<FinalizedQuery>)` to find the same and read the nodes one by one,
+/// while the response is received.
+/// * Use `Graph::exec(<String>)` to find anything using [Gremlin API](https://github.com/google/cayley/blob/master/docs/GremlinAPI.md) API
 /// from a prepared string. A raw, but not so beautiful, way to execute query.
+/// * Use `Graph::exec_json(<String>)` to execute a prepared string which passes arbitrary values
+/// to `g.Emit(...)` and get them as a JSON tree.
 /// * Use `Graph::save(<Path>)` to save a [Morphism](../path/struct.Morphism.html).
+/// * Use `Graph::find_mql(<&Mql>)` to find anything using [MQL](../mql/struct.Mql.html) template.
+/// * Use `Graph::shape(<FinalizedQuery>)` to get the [Shape](../shape/struct.Shape.html) of the query, without executing it.
+/// * Use `Graph::write(<&[Quad]>)` and `Graph::delete(<&[Quad]>)` to add or remove [Quads](./struct.Quad.html),
+/// or `Graph::write_idempotent(<&[Quad]>)` and `Graph::delete_idempotent(<&[Quad]>)` to allow them to be repeated.
+/// * Use `Graph::write_file(<&Path>, <progress>)` or `Graph::write_reader(<&mut Reader>, <progress>)`
+/// to upload a whole N-Quads document.
 pub struct Graph {
-    url: String
+    // scheme, host, port and path prefix, every API endpoint URL starts with
+    base_url: String,
+    version: CayleyAPIVersion,
+    pool: ConnectionPool,
+    retry: RetryPolicy,
+    breaker: Option<CircuitBreaker>
+}
+
+/// Settings of the connections to Cayley, pass them to `graph.with_config(...)`
+///
+/// Every timeout is `None` by default, which means there is no limit. When a timeout
+/// expires, the request fails with `Timeout` error.
+///
+/// ```
+/// use std::time::Duration;
+/// use std::default::Default;
+/// use cayley::graph::{Graph, GraphConfig};
+///
+/// let graph = Graph::default().unwrap()
+///                   .with_config(GraphConfig { connect_timeout: Some(Duration::seconds(1)),
+///                                              deadline: Some(Duration::seconds(30)),
+///                                              ..Default::default() });
+/// ```
+#[deriving(Clone, PartialEq, Show)]
+pub struct GraphConfig {
+    /// Time allowed to establish a connection
+    pub connect_timeout: Option<Duration>,
+    /// Time allowed for every single read from or write to the connection
+    pub read_timeout: Option<Duration>,
+    /// Time allowed for the whole request, from the connection till the last byte of the response
+    /// is read (so it also limits the iteration over the `find_iter(...)` results)
+    pub deadline: Option<Duration>,
+    /// Number of idle connections kept open to be re-used, `8` by default
+    pub max_idle: uint
 }
 
 /// A wrapper for a single item Cayley returned in response for a query
@@ -44,8 +117,76 @@ pub struct GraphNode(pub HashMap<String, String>);
 /// A collection of GraphNode instances
 pub struct GraphNodes(pub Vec<GraphNode>);
 
-/// Cayley API Version, planned to default to the latest, if it will ever change
-pub enum CayleyAPIVersion { V1, DefaultVersion }
+// the `result` field of a response with tag maps, `None` if it is `null`
+struct ResultNodes(Option<GraphNodes>);
+
+/// A result of a query, its shape is defined by the [finalizer](../path/trait.Query.html)
+/// the query was ended with
+pub enum QueryResult {
+    /// Returned for `.All()`, `.GetLimit(...)` and `.TagArray()`, tag maps of every item found
+    NodeMaps(GraphNodes),
+    /// Returned for `.ToArray()`, ids of every item found
+    NodeArray(Vec<String>),
+    /// Returned for `.ToValue()`, id of the first item found, if there was one
+    NodeValue(Option<String>),
+    /// Returned for `.TagValue()`, tag map of the first item found, if there was one
+    NodeMap(Option<GraphNode>),
+    /// Returned for `.ForEach(...)`, every value passed to `g.Emit(...)`, as is
+    Emitted(Vec<json::Json>),
+    /// Returned for any finalizer, when Cayley replied with `null` instead of the result,
+    /// i.e. when the start node is not in the database at all, while an empty result
+    /// (`NodeMaps` with no nodes, ...) means that nothing was found on the path
+    NullResult
+}
+
+/// Cayley API Version
+///
+/// `DefaultVersion` stands for `V1`, since it is supported by every Cayley release,
+/// while `V2` is supported only by the newer ones.
+///
+/// With `V2`, queries are sent to `/api/v2/query?lang=...`, quads are written to and deleted from
+/// `/api/v2/write` and `/api/v2/delete` as JSON, and N-Quads documents are uploaded to `/api/v2/write`
+/// as is, instead of a multipart form. Shape API has no `V2` alternative, so `Graph::shape` still uses
+/// `/api/v1/shape/...` there, while GraphQL has no `V1` alternative, so `Graph::find_graphql` fails
+/// with `NotSupported` error unless the graph uses `V2`.
+///
+/// Both versions reply with the `{"result": ...}` envelope (`{"data": ...}` for GraphQL), and report
+/// the failures either as `{"error": "..."}` or as a list of `{"errors": [{"message": "..."}]}`,
+/// both are returned as `ServerError` or `Refused` with the messages joined.
+pub enum CayleyAPIVersion { V1, V2, DefaultVersion }
+
+// Cayley API endpoints, URL of every endpoint is built from the base URL of a graph
+enum Endpoint {
+    QueryEndpoint(QueryLanguage),
+    ShapeEndpoint(QueryLanguage),
+    WriteEndpoint,
+    WriteFileEndpoint,
+    DeleteEndpoint,
+    GraphQLEndpoint
+}
+
+enum QueryLanguage { Gremlin, MQL }
+
+/// A single statement to write into or to delete from the database.
+///
+/// `label` is optional and is omitted from the request when it is `None`.
+#[deriving(Clone, PartialEq, Show)]
+pub struct Quad {
+    pub subject: String,
+    pub predicate: String,
+    pub object: String,
+    pub label: Option<String>
+}
+
+/// Cayley response to a write or delete request
+#[deriving(Clone, PartialEq, Show)]
+pub enum WriteResult {
+    /// Quads were accepted, contains the message server replied with
+    Written(String),
+    /// Quads were rejected, contains the error message server replied with; when the server
+    /// failed itself (replied with `5xx` status), the write fails with `ServerError` instead
+    Refused(String)
+}
 
 impl Graph {
 
@@ -60,15 +201,98 @@ impl Graph {
 
     /// Create a Graph which connects to the host you specified manually
     pub fn new(host: &str, port: int, version: CayleyAPIVersion) -> GraphResult<Graph> {
-        let version_str = match version { V1 | DefaultVersion => "v1" };
-        let url = format!("http://{:s}:{:d}/api/{:s}/query/gremlin",
-                          host, port, version_str);
-        Ok(Graph{ url: url })
+        Ok(Graph::at(format!("http://{:s}:{:d}", host, port), version))
+    }
+
+    // ---------------------------------- from_url -----------------------------
+
+    /// Create a Graph which connects to the URL you specified manually
+    ///
+    /// The path of the URL, if there is one, is used as a prefix for all the API
+    /// endpoints, so Cayley may be mounted behind a reverse proxy:
+    ///
+    /// ```
+    /// use cayley::{Graph, DefaultVersion};
+    /// // queries will be sent to http://example.com:8080/cayley/api/v1/query/gremlin
+    /// let graph = Graph::from_url("http://example.com:8080/cayley/", DefaultVersion).unwrap();
+    /// ```
+    pub fn from_url(url: &str, version: CayleyAPIVersion) -> GraphResult<Graph> {
+        match Url::parse(url) {
+            Err(error) => Err(InvalidUrl(error, url.to_string())),
+            Ok(parsed) => {
+                match parsed.scheme.as_slice() {
+                    "http" | "https" => (),
+                    _ => return Err(InvalidUrl(url::InvalidScheme, url.to_string()))
+                }
+                let host = match parsed.serialize_host() {
+                    Some(host) => host,
+                    None => return Err(InvalidUrl(url::EmptyHost, url.to_string()))
+                };
+                let port = match parsed.port_or_default() {
+                    Some(port) => port,
+                    None => return Err(InvalidUrl(url::InvalidPort, url.to_string()))
+                };
+                let prefix = match parsed.path() {
+                    Some(segments) => segments.iter().filter(|segment| !segment.is_empty())
+                                              .map(|segment| format!("/{:s}", *segment))
+                                              .collect::<Vec<String>>().concat(),
+                    None => String::new()
+                };
+                Ok(Graph::at(format!("{:s}://{:s}:{:u}{:s}", parsed.scheme, host, port, prefix),
+                             version))
+            }
+        }
+    }
+
+    // ---------------------------------- base_url -----------------------------
+
+    /// The URL every API endpoint URL of this Graph starts with: the scheme, the host,
+    /// the port and the path prefix, if there is one, without a trailing slash
+    ///
+    /// ```
+    /// use cayley::{Graph, DefaultVersion};
+    /// let graph = Graph::from_url("http://example.com/cayley/", DefaultVersion).unwrap();
+    /// assert_eq!(graph.base_url(), "http://example.com:80/cayley");
+    /// ```
+    pub fn base_url(&self) -> &str { self.base_url.as_slice() }
+
+    fn at(base_url: String, version: CayleyAPIVersion) -> Graph {
+        Graph { base_url: base_url, version: version,
+                pool: ConnectionPool::new(Default::default()),
+                retry: Default::default(), breaker: None }
+    }
+
+    // ---------------------------------- with_config --------------------------
+
+    /// Apply the timeouts and other [settings](./struct.GraphConfig.html) to the connections
+    /// of this Graph
+    pub fn with_config(mut self, config: GraphConfig) -> Graph {
+        self.pool.configure(config);
+        self
+    }
+
+    // ---------------------------------- with_retry ---------------------------
+
+    /// Replace the [policy](../retry/struct.RetryPolicy.html) of repeating the failed requests,
+    /// by default a read is made up to three times; use `RetryPolicy::none()` to never repeat them
+    pub fn with_retry(mut self, policy: RetryPolicy) -> Graph {
+        self.retry = policy;
+        self
+    }
+
+    // ---------------------------------- with_breaker -------------------------
+
+    /// Protect the requests with the [circuit breaker](../breaker/struct.CircuitBreaker.html),
+    /// so they fail at once with `CircuitOpen` error while Cayley is considered down
+    pub fn with_breaker(mut self, breaker: CircuitBreaker) -> Graph {
+        self.breaker = Some(breaker);
+        self
     }
 
     // ---------------------------------- find ---------------------------------
 
-    /// Find nodes with the Query implementation (say, Vertex-path) and return them parsed
+    /// Find nodes with the [finalized](../path/struct.FinalizedQuery.html) Query implementation (say,
+    /// Vertex-path) and return them parsed
     ///
     /// Since only [Vertex](../path/struct.Vertex.html) implements [Query](../path/trait.Query.html) trait
     /// following current spec, your code will look like that:
@@ -81,13 +305,232 @@ impl Graph {
     /// let graph = Graph::default().unwrap();
     /// graph.find(Vertex::start(Node("foo")).InP(Predicate("bar")).All()).unwrap();
     /// ```
-    pub fn find(&self, query: &Query) -> GraphResult<GraphNodes> {
-        if query.is_finalized() {
-            match query.compile() {
-                Some(compiled) => self.exec(compiled),
-                None => Err(QueryCompilationFailed)
+    ///
+    /// The variant of [QueryResult](./enum.QueryResult.html) returned matches the finalizer used:
+    ///
+    /// ```
+    /// use cayley::graph::{Graph, NodeArray};
+    /// use cayley::path::{Vertex, Path, Query};
+    /// use cayley::selector::{Predicate, Node};
+    ///
+    /// let graph = Graph::default().unwrap();
+    /// match graph.find(Vertex::start(Node("foo")).InP(Predicate("bar")).ToArray()) {
+    ///     Ok(NodeArray(ids)) => println!("{}", ids),
+    ///     Ok(_) => panic!("ToArray() should return an array of ids"),
+    ///     Err(error) => panic!(error.to_string())
+    /// }
+    /// ```
+    pub fn find(&self, query: FinalizedQuery) -> GraphResult<QueryResult> {
+        match query.compile() {
+            Err(error) => Err(error),
+            Ok(compiled) => {
+                match self.perform_query(QueryEndpoint(Gremlin), compiled) {
+                    Ok(body) => Graph::decode_result(query.finalizer(), body),
+                    Err(error) => Err(error)
+                }
             }
-        } else { Err(QueryNotFinalized) }
+        }
+    }
+
+    // ---------------------------------- find_as ------------------------------
+
+    /// Find nodes with the query and decode every one of them into a structure, which fields
+    /// are named after the tags of the node, see [NodeDecoder](../decode/struct.NodeDecoder.html)
+    ///
+    /// For `.ToArray()` and `.ToValue()` the nodes have just the `id` tag, and for `.ForEach(...)`
+    /// every emitted value is expected to be a flat object.
+    ///
+    /// ```
+    /// extern crate serialize;
+    /// extern crate cayley;
+    ///
+    /// use cayley::graph::Graph;
+    /// use cayley::path::{Vertex, Path, Query};
+    /// use cayley::selector::{Predicate, Node, Tag};
+    ///
+    /// #[deriving(Decodable)]
+    /// struct Film { id: String, name: String }
+    ///
+    /// fn main() {
+    ///     let graph = Graph::default().unwrap();
+    ///     let films: Vec<Film> = graph.find_as(Vertex::start(Node("Casablanca"))
+    ///                                                  .As(Tag("name"))
+    ///                                                  .InP(Predicate("name"))
+    ///                                                  .All()).unwrap();
+    ///     println!("{}", films[0].id);
+    /// }
+    /// ```
+    pub fn find_as<T: Decodable<NodeDecoder, NodeDecoderError>>(&self, query: FinalizedQuery)
+                                                               -> GraphResult<Vec<T>> {
+        let nodes = match self.find(query) {
+            Err(error) => return Err(error),
+            Ok(NodeMaps(GraphNodes(nodes))) => nodes,
+            Ok(NodeMap(node)) => node.into_iter().collect(),
+            Ok(NodeArray(ids)) => ids.into_iter().map(|id| GraphNode::with_id(id)).collect(),
+            Ok(NodeValue(id)) => id.into_iter().map(|id| GraphNode::with_id(id)).collect(),
+            Ok(NullResult) => Vec::new(),
+            Ok(Emitted(values)) => {
+                let mut nodes = Vec::with_capacity(values.len());
+                for value in values.into_iter() {
+                    nodes.push(try!(Graph::decode_json(value)));
+                }
+                nodes
+            }
+        };
+        let mut decoded = Vec::with_capacity(nodes.len());
+        for node in nodes.into_iter() {
+            match decode::from_node(node) {
+                Ok(item) => decoded.push(item),
+                Err(error) => return Err(NodeDecodingFailed(error))
+            }
+        }
+        Ok(decoded)
+    }
+
+    // ---------------------------------- find_compact -------------------------
+
+    /// Find nodes with the query and store them in [CompactNodes](../compact/struct.CompactNodes.html),
+    /// which keep every tag name once and all the values in a single buffer, instead of a map
+    /// of owned strings for every node
+    ///
+    /// ```
+    /// use cayley::graph::Graph;
+    /// use cayley::path::{Vertex, Query};
+    /// use cayley::selector::AnyNode;
+    ///
+    /// let graph = Graph::default().unwrap();
+    /// let nodes = graph.find_compact(Vertex::start(AnyNode).All()).unwrap();
+    /// println!("{} nodes found", nodes.len());
+    /// ```
+    pub fn find_compact(&self, query: FinalizedQuery) -> GraphResult<CompactNodes> {
+        match query.compile() {
+            Err(error) => Err(error),
+            Ok(compiled) => {
+                match self.perform_query(QueryEndpoint(Gremlin), compiled) {
+                    Ok(body) => match str::from_utf8(body.as_slice()) {
+                        None => Err(ResponseParseFailed),
+                        Some(body_json) => CompactNodes::from_json(body_json)
+                    },
+                    Err(error) => Err(error)
+                }
+            }
+        }
+    }
+
+    // ---------------------------------- find_iter ----------------------------
+
+    /// Find nodes with the query and read them one by one, while the response is received,
+    /// so only a single node is stored at a time, see [GraphNodesIter](../stream/struct.GraphNodesIter.html)
+    ///
+    /// ```
+    /// use cayley::graph::Graph;
+    /// use cayley::path::{Vertex, Query};
+    /// use cayley::selector::AnyNode;
+    ///
+    /// let graph = Graph::default().unwrap();
+    /// let found = graph.find_iter(Vertex::start(AnyNode).All()).unwrap()
+    ///                  .take_while(|node| node.is_ok()).count();
+    /// println!("{} nodes found", found);
+    /// ```
+    pub fn find_iter(&self, query: FinalizedQuery)
+                     -> GraphResult<GraphNodesIter<BufferedReader<ResponseBody>>> {
+        match query.compile() {
+            Err(error) => Err(error),
+            Ok(compiled) => {
+                // only the start of the response is repeated, not the iteration over the nodes
+                self.retry.run(|_| self.guarded(QueryEndpoint(Gremlin),
+                                                || self.open_nodes(compiled.clone())))
+            }
+        }
+    }
+
+    // sends the query and checks the start of the response, the nodes are not read yet
+    fn open_nodes(&self, query: String) -> GraphResult<GraphNodesIter<BufferedReader<ResponseBody>>> {
+        match self.open_request(QueryEndpoint(Gremlin), query.clone(), true) {
+            Err(error) => Err(error),
+            Ok(Response { status, body }) => {
+                let mut reader = BufferedReader::new(body);
+                // the beginning of the body is enough to tell if the query was rejected
+                let rejected = match reader.fill_buf() {
+                    Ok(start) => Graph::is_rejected(status, start),
+                    Err(_) => status >= 400
+                };
+                match rejected {
+                    false => Ok(GraphNodesIter::new(reader, query)),
+                    true => match reader.read_to_end() {
+                        Ok(body) => Err(Graph::server_error(status, body, query)),
+                        Err(error) => Err(request_failed(error, query))
+                    }
+                }
+            }
+        }
+    }
+
+    // ---------------------------------- find_mql -----------------------------
+
+    /// Find data with the [MQL](../mql/struct.Mql.html) query and return the filled template,
+    /// as a JSON tree
+    ///
+    /// ```
+    /// use cayley::Graph;
+    /// use cayley::mql;
+    /// use cayley::mql::Mql;
+    ///
+    /// let graph = Graph::default().unwrap();
+    /// graph.find_mql(&Mql::new(mql::object(vec!(("id", mql::any()),
+    ///                                           ("name", mql::value("Casablanca")))))).unwrap();
+    /// ```
+    pub fn find_mql(&self, query: &Mql) -> GraphResult<json::Json> {
+        let compiled = query.compile();
+        match self.perform_query(QueryEndpoint(MQL), compiled) {
+            Ok(body) => Graph::decode_json_result(body),
+            Err(error) => Err(error)
+        }
+    }
+
+    // ---------------------------------- find_graphql -------------------------
+
+    /// Find data with the [GraphQL](https://github.com/google/cayley/blob/master/docs/GraphQL.md)
+    /// query and return its `data`, as a JSON tree
+    ///
+    /// GraphQL is supported by the newer Cayley servers only, through `/api/v2/query?lang=graphql`,
+    /// so the graph must use `V2`, otherwise the query is not sent and `NotSupported` error is
+    /// returned.
+    ///
+    /// ```
+    /// use cayley::{Graph, V2};
+    /// let graph = Graph::new("localhost", 64210, V2).unwrap();
+    /// graph.find_graphql("{ nodes(id: \"<alice>\") { id, follows: <follows> { id } } }").unwrap();
+    /// ```
+    pub fn find_graphql(&self, query: &str) -> GraphResult<json::Json> {
+        match self.perform_query(GraphQLEndpoint, query.to_string()) {
+            Ok(body) => Graph::decode_graphql_data(body, query.to_string()),
+            Err(error) => Err(error)
+        }
+    }
+
+    // ---------------------------------- shape --------------------------------
+
+    /// Get the [Shape](../shape/struct.Shape.html) of the query, the tree of iterators
+    /// Cayley would build to execute it, without executing the query itself
+    ///
+    /// ```
+    /// use cayley::graph::Graph;
+    /// use cayley::path::{Vertex, Path, Query};
+    /// use cayley::selector::{Predicate, Node};
+    ///
+    /// let graph = Graph::default().unwrap();
+    /// let shape = graph.shape(Vertex::start(Node("foo")).InP(Predicate("bar")).All()).unwrap();
+    /// println!("{} nodes, {} links", shape.nodes.len(), shape.links.len());
+    /// ```
+    pub fn shape(&self, query: FinalizedQuery) -> GraphResult<Shape> {
+        match query.compile() {
+            Ok(compiled) => match self.perform_query(ShapeEndpoint(Gremlin), compiled) {
+                Ok(body) => Graph::decode_body(body),
+                Err(error) => Err(error)
+            },
+            Err(error) => Err(error)
+        }
     }
 
     // ---------------------------------- exec ---------------------------------
@@ -98,32 +541,172 @@ impl Graph {
     /// the string concatenation performed with `path::` module members, this
     /// method is for you.
     ///
+    /// The result is read as the one of `.All()`, i.e. `NodeMaps`, or `NullResult` when Cayley
+    /// replied with `null` instead of it, see [QueryResult](./enum.QueryResult.html).
+    ///
+    /// ```
+    /// use cayley::{Graph, NodeMaps, GraphNodes};
+    /// let graph = Graph::default().unwrap();
+    /// match graph.exec("g.V(\"foo\").In(\"bar\").All()".to_string()).unwrap() {
+    ///     NodeMaps(GraphNodes(nodes)) => println!("{} nodes found", nodes.len()),
+    ///     _ => println!("foo is not in the database")
+    /// }
+    /// ```
+    pub fn exec(&self, query: String) -> GraphResult<QueryResult> {
+        match self.perform_query(QueryEndpoint(Gremlin), query) {
+            Ok(body) => Graph::decode_result(&All, body),
+            Err(error) => Err(error)
+        }
+    }
+
+    // ---------------------------------- exec_json ----------------------------
+
+    /// Execute raw pre-compiled query string and return its `result` as a JSON tree
+    ///
+    /// Unlike `exec`, makes no assumptions on the shape of the result, so it fits the
+    /// scripts which pass numbers, arrays or nested objects to `g.Emit(...)`.
+    ///
     /// ```
     /// use cayley::Graph;
     /// let graph = Graph::default().unwrap();
-    /// graph.exec("g.V(\"foo\").In(\"bar\").All()".to_string()).unwrap();
+    /// graph.exec_json("g.Emit({ total: g.V(\"foo\").In(\"bar\").ToArray().length })".to_string()).unwrap();
     /// ```
-    pub fn exec(&self, query: String) -> GraphResult<GraphNodes> {
-        println!("Executing query: {:s}", query);
-        match self.perform_request(query) {
-            Ok(body) => Graph::decode_nodes(body),
+    pub fn exec_json(&self, query: String) -> GraphResult<json::Json> {
+        match self.perform_query(QueryEndpoint(Gremlin), query) {
+            Ok(body) => Graph::decode_json_result(body),
             Err(error) => Err(error)
         }
     }
 
-    // uses RequestWriter to perform a request with given request body and returns the response body
-    fn perform_request(&self, body: String) -> GraphResult<Vec<u8>> {
-        match Graph::prepare_request(self.url.as_slice()) {
-            Err(error) => Err(error),
+    // ---------------------------------- write --------------------------------
+
+    /// Write the given quads into the database
+    ///
+    /// ```
+    /// use cayley::{Graph, Quad};
+    /// let graph = Graph::default().unwrap();
+    /// graph.write([Quad::new("foo", "follows", "bar")].as_slice()).unwrap();
+    /// ```
+    pub fn write(&self, quads: &[Quad]) -> GraphResult<WriteResult> {
+        self.send_quads(WriteEndpoint, quads, false)
+    }
+
+    // ---------------------------------- write_idempotent ---------------------
+
+    /// Write the given quads into the database, repeating the request if it fails, just like
+    /// the reads are repeated, see [RetryPolicy](../retry/struct.RetryPolicy.html)
+    ///
+    /// A write is never repeated by `write(...)`, since the request could reach Cayley before
+    /// it failed. Use this method when writing the same quads twice does no harm.
+    ///
+    /// ```
+    /// use cayley::{Graph, Quad};
+    /// let graph = Graph::default().unwrap();
+    /// graph.write_idempotent([Quad::new("foo", "follows", "bar")].as_slice()).unwrap();
+    /// ```
+    pub fn write_idempotent(&self, quads: &[Quad]) -> GraphResult<WriteResult> {
+        self.send_quads(WriteEndpoint, quads, true)
+    }
+
+    // ---------------------------------- delete -------------------------------
+
+    /// Delete the given quads from the database
+    ///
+    /// ```
+    /// use cayley::{Graph, Quad};
+    /// let graph = Graph::default().unwrap();
+    /// graph.delete([Quad::new("foo", "follows", "bar")].as_slice()).unwrap();
+    /// ```
+    pub fn delete(&self, quads: &[Quad]) -> GraphResult<WriteResult> {
+        self.send_quads(DeleteEndpoint, quads, false)
+    }
+
+    // ---------------------------------- delete_idempotent --------------------
+
+    /// Delete the given quads from the database, repeating the request if it fails,
+    /// see `write_idempotent`
+    ///
+    /// ```
+    /// use cayley::{Graph, Quad};
+    /// let graph = Graph::default().unwrap();
+    /// graph.delete_idempotent([Quad::new("foo", "follows", "bar")].as_slice()).unwrap();
+    /// ```
+    pub fn delete_idempotent(&self, quads: &[Quad]) -> GraphResult<WriteResult> {
+        self.send_quads(DeleteEndpoint, quads, true)
+    }
+
+    // ---------------------------------- write_file ---------------------------
+
+    /// Upload an N-Quads document from the file at the given path
+    ///
+    /// The file is streamed to the server chunk by chunk, and `progress` is called
+    /// after every chunk sent, with the number of bytes and quads passed so far.
+    ///
+    /// ```
+    /// use cayley::Graph;
+    /// let graph = Graph::default().unwrap();
+    /// graph.write_file(&Path::new("./30kmoviedata.nq"), |progress| {
+    ///     println!("{} bytes, {} quads", progress.bytes, progress.quads);
+    /// }).unwrap();
+    /// ```
+    pub fn write_file(&self, path: &Path, progress: |UploadProgress|) -> GraphResult<WriteResult> {
+        let filename = path.display().to_string();
+        match File::open(path) {
+            Err(error) => Err(ReadFailed(error, filename)),
+            Ok(mut file) => self.upload(&mut file, filename.as_slice(), progress)
+        }
+    }
+
+    // ---------------------------------- write_reader -------------------------
+
+    /// Upload an N-Quads document read from any `Reader`, see `write_file` for details
+    pub fn write_reader<R: Reader>(&self, reader: &mut R, progress: |UploadProgress|)
+                                   -> GraphResult<WriteResult> {
+        self.upload(reader, "quads.nq", progress)
+    }
+
+    // uploads a document, if the circuit breaker lets it through, it is never repeated
+    fn upload<R: Reader>(&self, reader: &mut R, filename: &str, progress: |UploadProgress|)
+                         -> GraphResult<WriteResult> {
+        self.guarded(WriteFileEndpoint,
+                     || self.send_document(&mut *reader, filename, |passed| progress(passed)))
+    }
+
+    // streams a document from the reader, as a multipart form for V1 and as is for V2,
+    // and decodes the server answer
+    fn send_document<R: Reader>(&self, reader: &mut R, filename: &str, progress: |UploadProgress|)
+                                -> GraphResult<WriteResult> {
+        let boundary = upload::boundary();
+        let content_type = match self.version {
+            V2 => media_type("application", "n-quads"),
+            V1 | DefaultVersion => {
+                let mut content_type = media_type("multipart", "form-data");
+                content_type.parameters.push(("boundary".to_string(), boundary.clone()));
+                content_type
+            }
+        };
+        let request = try!(self.prepare_request(WriteFileEndpoint));
+        match request.post_chunked(content_type) {
+            Err(error) => Err(request_failed(error, filename.to_string())),
             Ok(mut request) => {
-                request.headers.content_length = Some(body.len());
-                match request.write_str(body.as_slice()) {
-                    Err(error) => Err(RequestFailed(error, body)),
-                    Ok(_) => match request.read_response() {
-                        Err((_, error)) => Err(RequestFailed(error, body)),
-                        Ok(mut response) => match response.read_to_end() {
-                            Err(error) => Err(RequestFailed(error, body)),
-                            Ok(response_body) => Ok(response_body)
+                let streamed = match self.version {
+                    V2 => upload::stream_raw(reader, &mut request, progress),
+                    V1 | DefaultVersion =>
+                        upload::stream_multipart(reader, &mut request, boundary.as_slice(),
+                                                 filename, progress)
+                };
+                match streamed {
+                    Err(SourceFailed(error)) => Err(ReadFailed(error, filename.to_string())),
+                    Err(TargetFailed(error)) => Err(request_failed(error, filename.to_string())),
+                    Ok(_) => match request.finish() {
+                        Err(error) => Err(request_failed(error, filename.to_string())),
+                        Ok(Response { status, body: mut response }) => match response.read_to_end() {
+                            Err(error) => Err(request_failed(error, filename.to_string())),
+                            Ok(response_body) => match Graph::is_failed(status) {
+                                true => Err(Graph::server_error(status, response_body,
+                                                                filename.to_string())),
+                                false => Graph::decode_write_result(response_body)
+                            }
                         }
                     }
                 }
@@ -131,27 +714,301 @@ impl Graph {
         }
     }
 
-    // prepares the RequestWriter object from URL to save it inside the Graph for future re-use
-    fn prepare_request(url: &str) -> GraphResult<Box<RequestWriter>> {
-        match Url::parse(url) {
-            Err(error) => Err(InvalidUrl(error, url.to_string())),
-            Ok(parsed_url) => {
-                match RequestWriter::new(Post, parsed_url) {
-                    Err(error) => Err(MalformedRequest(error, url.to_string())),
-                    Ok(request) => Ok(box request)
+    // posts quads encoded as a JSON list to the given endpoint and decodes the server answer,
+    // the request is repeated, by the retry policy or through a new connection, only if the
+    // caller marked it as idempotent
+    fn send_quads(&self, endpoint: Endpoint, quads: &[Quad], idempotent: bool)
+                  -> GraphResult<WriteResult> {
+        let body = json::encode(&quads);
+        let response = match idempotent {
+            true => self.retry.run(|_| self.guarded(endpoint,
+                                                    || self.perform_write(endpoint, body.clone(), true))),
+            false => self.guarded(endpoint, || self.perform_write(endpoint, body.clone(), false))
+        };
+        match response {
+            Ok(body) => Graph::decode_write_result(body),
+            Err(error) => Err(error)
+        }
+    }
+
+    // performs a write request and returns the response body, unless the server failed itself;
+    // then the write is repeated, if it may be, and counted by the circuit breaker, while the
+    // writes Cayley refused are decoded from the body as `Refused`
+    fn perform_write(&self, endpoint: Endpoint, body: String, resend: bool) -> GraphResult<Vec<u8>> {
+        match self.perform_request(endpoint, body.clone(), resend) {
+            Err(error) => Err(error),
+            Ok((status, response)) => match Graph::is_failed(status) {
+                true => Err(Graph::server_error(status, response, body)),
+                false => Ok(response)
+            }
+        }
+    }
+
+    // builds the full URL of the given API endpoint, fails if the API version has no such endpoint
+    fn url_for(&self, endpoint: Endpoint) -> GraphResult<String> {
+        let path = match self.version {
+            V1 | DefaultVersion => match endpoint {
+                QueryEndpoint(Gremlin) => "api/v1/query/gremlin",
+                QueryEndpoint(MQL) => "api/v1/query/mql",
+                ShapeEndpoint(Gremlin) => "api/v1/shape/gremlin",
+                ShapeEndpoint(MQL) => "api/v1/shape/mql",
+                WriteEndpoint => "api/v1/write",
+                WriteFileEndpoint => "api/v1/write/file/nquad",
+                DeleteEndpoint => "api/v1/delete",
+                // there is no GraphQL in V1
+                GraphQLEndpoint => return Err(NotSupported("GraphQL query".to_string()))
+            },
+            V2 => match endpoint {
+                QueryEndpoint(Gremlin) => "api/v2/query?lang=gremlin",
+                QueryEndpoint(MQL) => "api/v2/query?lang=mql",
+                // there is no shape endpoint in V2
+                ShapeEndpoint(Gremlin) => "api/v1/shape/gremlin",
+                ShapeEndpoint(MQL) => "api/v1/shape/mql",
+                WriteEndpoint | WriteFileEndpoint => "api/v2/write",
+                DeleteEndpoint => "api/v2/delete",
+                GraphQLEndpoint => "api/v2/query?lang=graphql"
+            }
+        };
+        Ok(format!("{:s}/{:s}", self.base_url, path))
+    }
+
+    // V2 selects the format of the written data by its content type
+    fn content_type_for(&self, endpoint: Endpoint) -> Option<MediaType> {
+        match (self.version, endpoint) {
+            (V2, WriteEndpoint) | (V2, DeleteEndpoint) => Some(media_type("application", "json")),
+            (V2, WriteFileEndpoint) => Some(media_type("application", "n-quads")),
+            (_, _) => None
+        }
+    }
+
+    // performs a request with given request body and returns the response status and body
+    fn perform_request(&self, endpoint: Endpoint, body: String, resend: bool)
+                       -> GraphResult<(u16, Vec<u8>)> {
+        match self.open_request(endpoint, body.clone(), resend) {
+            Err(error) => Err(error),
+            Ok(Response { status, body: mut response }) => match response.read_to_end() {
+                Err(error) => Err(request_failed(error, body)),
+                Ok(response_body) => Ok((status, response_body))
+            }
+        }
+    }
+
+    // performs a query request and returns the response body, if the query was not rejected;
+    // queries do not change the data, so they are repeated following the retry policy
+    fn perform_query(&self, endpoint: Endpoint, query: String) -> GraphResult<Vec<u8>> {
+        self.retry.run(|_| self.guarded(endpoint, || {
+            match self.perform_request(endpoint, query.clone(), true) {
+                Err(error) => Err(error),
+                Ok((status, body)) => match Graph::is_rejected(status, body.as_slice()) {
+                    true => Err(Graph::server_error(status, body, query.clone())),
+                    false => Ok(body)
                 }
             }
+        }))
+    }
+
+    // passes the request to the endpoint through the circuit breaker, if there is one
+    fn guarded<T>(&self, endpoint: Endpoint, operation: || -> GraphResult<T>) -> GraphResult<T> {
+        match self.breaker {
+            Some(ref breaker) => {
+                let url = try!(self.url_for(endpoint));
+                breaker.call(url.as_slice(), operation)
+            },
+            None => operation()
         }
     }
 
-    // extract JSON nodes from response
-    fn decode_nodes(source: Vec<u8>) -> GraphResult<GraphNodes> {
+    // sends a request with given request body and returns the response, which body is not read yet;
+    // with `resend`, the request may be sent once again if the pooled connection was closed
+    fn open_request(&self, endpoint: Endpoint, body: String, resend: bool) -> GraphResult<Response> {
+        let request = try!(self.prepare_request(endpoint));
+        match request.post(self.content_type_for(endpoint), body.as_bytes(), resend) {
+            Err(error) => Err(request_failed(error, body)),
+            Ok(response) => Ok(response)
+        }
+    }
+
+    // prepares the request to the endpoint, to be sent through the pool of connections
+    fn prepare_request<'a>(&'a self, endpoint: Endpoint) -> GraphResult<PooledRequest<'a>> {
+        let url = try!(self.url_for(endpoint));
+        match Url::parse(url.as_slice()) {
+            Err(error) => Err(InvalidUrl(error, url)),
+            Ok(parsed_url) => match self.pool.prepare(parsed_url) {
+                Err(error) => Err(MalformedRequest(error, url)),
+                Ok(request) => Ok(request)
+            }
+        }
+    }
+
+    // the server (or a proxy in front of it) failed itself, it is not the request to blame
+    fn is_failed(status: u16) -> bool {
+        status >= 500
+    }
+
+    // Cayley replies with `{"error": ...}` or `{"errors": [...]}` when it rejects a query,
+    // usually with a `4xx` or `5xx` status; other successful statuses (like `203` from a proxy)
+    // are not a rejection by themselves
+    fn is_rejected(status: u16, body: &[u8]) -> bool {
+        if status >= 400 { return true; }
+        let start = String::from_utf8_lossy(body.slice_to(cmp::min(body.len(), 64)));
+        let trimmed = start.as_slice().trim_left();
+        trimmed.starts_with("{") && trimmed.slice_from(1).trim_left().starts_with("\"error\"")
+    }
+
+    // extract the message from the `{"error": ...}` or `{"errors": [...]}` response, or take
+    // the whole body as a message
+    fn server_error(status: u16, body: Vec<u8>, query: String) -> GraphRequestError {
+        let text = String::from_utf8_lossy(body.as_slice()).into_string();
+        let message = match json::from_str(text.as_slice()) {
+            Ok(response) => Graph::error_message(&response).unwrap_or(text.clone()),
+            Err(_) => text.clone()
+        };
+        ServerError(status, message, query)
+    }
+
+    // the message of `{"error": "..."}` response, or the messages of
+    // `{"errors": ["...", {"message": "..."}]}` one joined, `None` if there are none
+    fn error_message(response: &json::Json) -> Option<String> {
+        match (response.find(&"error".to_string()), response.find(&"errors".to_string())) {
+            (Some(&json::String(ref message)), _) => Some(message.clone()),
+            (_, Some(&json::List(ref errors))) if !errors.is_empty() => {
+                let messages: Vec<String> = errors.iter().map(|error| match *error {
+                    json::String(ref message) => message.clone(),
+                    _ => match error.find(&"message".to_string()) {
+                        Some(&json::String(ref message)) => message.clone(),
+                        _ => error.to_string()
+                    }
+                }).collect();
+                Some(messages.connect("; "))
+            },
+            (_, _) => None
+        }
+    }
+
+    // decode the whole response, i.e. JSON nodes or a query shape
+    fn decode_body<T: Decodable<json::Decoder, json::DecoderError>>(source: Vec<u8>)
+                                                                    -> GraphResult<T> {
+        match str::from_utf8(source.as_slice()) {
+            None => Err(ResponseParseFailed),
+            Some(body_json) => {
+                match json_decode(body_json) {
+                    Err(error) => Err(DecodingFailed(error, body_json.to_string())),
+                    Ok(decoded) => Ok(decoded)
+                }
+            }
+        }
+    }
+
+    // extract the result in the shape defined by the finalizer
+    fn decode_result(finalizer: &Finalizer, source: Vec<u8>) -> GraphResult<QueryResult> {
+        match *finalizer {
+            All | GetLimit(_) => Graph::decode_body(source).map(|ResultNodes(nodes)| match nodes {
+                Some(nodes) => NodeMaps(nodes),
+                None => NullResult
+            }),
+            ToArray | ToValue | TagArray | TagValue => Graph::decode_emitted(finalizer, source),
+            ForEach(..) => Graph::decode_values(source).map(|values| match values {
+                Some(values) => Emitted(values),
+                None => NullResult
+            })
+        }
+    }
+
+    // extract all the values passed to `g.Emit(...)` without any assumptions on their type,
+    // `None` if the result is `null`
+    fn decode_values(source: Vec<u8>) -> GraphResult<Option<Vec<json::Json>>> {
+        match Graph::decode_json_result(source) {
+            Err(error) => Err(error),
+            Ok(json::List(values)) => Ok(Some(values)),
+            Ok(json::Null) => Ok(None),
+            Ok(_) => Err(ResponseParseFailed)
+        }
+    }
+
+    // extract the value the query passed to `g.Emit(...)`, see `Finalizer`
+    fn decode_emitted(finalizer: &Finalizer, source: Vec<u8>) -> GraphResult<QueryResult> {
+        let emitted = match Graph::decode_values(source) {
+            Err(error) => return Err(error),
+            Ok(None) => return Ok(NullResult),
+            Ok(Some(values)) => values.into_iter().next()
+        };
+        match (finalizer, emitted) {
+            (&ToArray, None) => Ok(NodeArray(Vec::new())),
+            (&ToArray, Some(ids)) => Graph::decode_json(ids).map(|ids| NodeArray(ids)),
+            (&ToValue, None) => Ok(NodeValue(None)),
+            (&ToValue, Some(id)) => Graph::decode_json(id).map(|id| NodeValue(id)),
+            (&TagArray, None) => Ok(NodeMaps(GraphNodes(Vec::new()))),
+            (&TagArray, Some(nodes)) =>
+                Graph::decode_json(nodes).map(|nodes| NodeMaps(GraphNodes(nodes))),
+            (&TagValue, None) => Ok(NodeMap(None)),
+            (&TagValue, Some(node)) => Graph::decode_json(node).map(|node| NodeMap(node)),
+            (_, _) => Err(ResponseParseFailed)
+        }
+    }
+
+    // parse the response as is and take the value of its `result` field, `Null` if there is none
+    fn decode_json_result(source: Vec<u8>) -> GraphResult<json::Json> {
+        match str::from_utf8(source.as_slice()) {
+            None => Err(ResponseParseFailed),
+            Some(result_json) => {
+                match json::from_str(result_json) {
+                    Err(error) => Err(DecodingFailed(json::ParseError(error),
+                                                     result_json.to_string())),
+                    Ok(json::Object(mut response)) =>
+                        Ok(response.pop(&"result".to_string()).unwrap_or(json::Null)),
+                    Ok(_) => Err(ResponseParseFailed)
+                }
+            }
+        }
+    }
+
+    // take the `data` of the GraphQL response, which may report the errors along with it
+    fn decode_graphql_data(source: Vec<u8>, query: String) -> GraphResult<json::Json> {
+        match str::from_utf8(source.as_slice()) {
+            None => Err(ResponseParseFailed),
+            Some(data_json) => {
+                match json::from_str(data_json) {
+                    Err(error) => Err(DecodingFailed(json::ParseError(error), data_json.to_string())),
+                    // the rejected queries are caught before, so the status is `200` here
+                    Ok(response) => {
+                        let message = Graph::error_message(&response);
+                        match (message, response) {
+                            (Some(message), _) => Err(ServerError(200, message, query)),
+                            (None, json::Object(mut response)) =>
+                                Ok(response.pop(&"data".to_string()).unwrap_or(json::Null)),
+                            (None, _) => Err(ResponseParseFailed)
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // decode a part of the response into the expected type
+    fn decode_json<T: Decodable<json::Decoder, json::DecoderError>>(value: json::Json)
+                                                                    -> GraphResult<T> {
+        let source = value.to_string();
+        let mut decoder = json::Decoder::new(value);
+        match Decodable::decode(&mut decoder) {
+            Err(error) => Err(DecodingFailed(error, source)),
+            Ok(decoded) => Ok(decoded)
+        }
+    }
+
+    // extract the message from `{"result": ...}` or `{"error": ...}` write response
+    fn decode_write_result(source: Vec<u8>) -> GraphResult<WriteResult> {
         match str::from_utf8(source.as_slice()) {
             None => Err(ResponseParseFailed),
-            Some(nodes_json) => {
-                match json_decode(nodes_json) {
-                    Err(error) => Err(DecodingFailed(error, nodes_json.to_string())),
-                    Ok(nodes) => Ok(nodes)
+            Some(response_json) => {
+                match json::from_str(response_json) {
+                    Err(error) => Err(DecodingFailed(json::ParseError(error),
+                                                     response_json.to_string())),
+                    Ok(response) => match (response.find(&"result".to_string()),
+                                           Graph::error_message(&response)) {
+                        (Some(&json::String(ref message)), _) => Ok(Written(message.clone())),
+                        (_, Some(message)) => Ok(Refused(message)),
+                        _ => Err(ResponseParseFailed)
+                    }
                 }
             }
         }
@@ -159,6 +1016,62 @@ impl Graph {
 
 }
 
+fn media_type(type_: &str, subtype: &str) -> MediaType {
+    MediaType { type_: type_.to_string(), subtype: subtype.to_string(), parameters: Vec::new() }
+}
+
+impl Default for GraphConfig {
+    fn default() -> GraphConfig {
+        GraphConfig { connect_timeout: None, read_timeout: None, deadline: None, max_idle: 8 }
+    }
+}
+
+impl Quad {
+
+    /// Create a Quad with no label
+    pub fn new(subject: &str, predicate: &str, object: &str) -> Quad {
+        Quad { subject: subject.to_string(),
+               predicate: predicate.to_string(),
+               object: object.to_string(),
+               label: None }
+    }
+
+    /// Create a Quad with a label
+    pub fn labeled(subject: &str, predicate: &str, object: &str, label: &str) -> Quad {
+        Quad { subject: subject.to_string(),
+               predicate: predicate.to_string(),
+               object: object.to_string(),
+               label: Some(label.to_string()) }
+    }
+
+}
+
+impl<S: Encoder<E>, E> Encodable<S, E> for Quad {
+    fn encode(&self, encoder: &mut S) -> Result<(), E> {
+        let len = match self.label { Some(_) => 4, None => 3 };
+        encoder.emit_struct("Quad", len, |encoder| {
+            try!(encoder.emit_struct_field("subject", 0, |encoder| self.subject.encode(encoder)));
+            try!(encoder.emit_struct_field("predicate", 1, |encoder| self.predicate.encode(encoder)));
+            try!(encoder.emit_struct_field("object", 2, |encoder| self.object.encode(encoder)));
+            match self.label {
+                Some(ref label) => encoder.emit_struct_field("label", 3, |encoder| label.encode(encoder)),
+                None => Ok(())
+            }
+        })
+    }
+}
+
+impl GraphNode {
+
+    // a node which has only the `id` tag
+    fn with_id(id: String) -> GraphNode {
+        let mut tags = HashMap::new();
+        tags.insert("id".to_string(), id);
+        GraphNode(tags)
+    }
+
+}
+
 impl<S: Decoder<E>, E> Decodable<S, E> for GraphNode {
     fn decode(decoder: &mut S) -> Result<GraphNode, E> {
         decoder.read_map(|decoder, len| {
@@ -178,11 +1091,21 @@ impl<S: Decoder<E>, E> Decodable<S, E> for GraphNode {
 
 impl<S: Decoder<E>, E> Decodable<S, E> for GraphNodes {
     fn decode(decoder: &mut S) -> Result<GraphNodes, E> {
+        // `null` result is read as no nodes, `Graph::find` and `Graph::exec` tell it apart with `NullResult`
+        match Decodable::decode(decoder) {
+            Ok(ResultNodes(nodes)) => Ok(nodes.unwrap_or(GraphNodes(Vec::new()))),
+            Err(err) => Err(err)
+        }
+    }
+}
+
+impl<S: Decoder<E>, E> Decodable<S, E> for ResultNodes {
+    fn decode(decoder: &mut S) -> Result<ResultNodes, E> {
         decoder.read_struct("__unused__", 0, |decoder| {
             decoder.read_struct_field("result", 0, |decoder| {
                 decoder.read_option(|decoder, has_value| {
                     match has_value {
-                        false => Ok(GraphNodes(Vec::new())), /* FIXME: return GraphNodes(None) */
+                        false => Ok(ResultNodes(None)),
                         true => decoder.read_seq(|decoder, len| {
                             let mut nodes: Vec<GraphNode> = Vec::with_capacity(len);
                             for i in range(0u, len) {
@@ -192,7 +1115,7 @@ impl<S: Decoder<E>, E> Decodable<S, E> for GraphNodes {
                                     Err(err) => return Err(err)
                                 });
                             };
-                            Ok(GraphNodes(nodes))
+                            Ok(ResultNodes(Some(GraphNodes(nodes))))
                         })
                     }
                 })
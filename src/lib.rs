@@ -28,11 +28,32 @@
 //! };
 //! ```
 //!
-//! For the moment, this code performs no connection at all, when you only create a Graph.
-//! On the other hand, the connection is established for every query. So this error, if happened,
+//! Or, if Cayley is mounted under some path behind a reverse proxy, use its URL:
+//!
+//! ```
+//! use cayley::{Graph, DefaultVersion};
+//! let graph = Graph::from_url("http://example.com/cayley/", DefaultVersion).unwrap();
+//! ```
+//!
+//! Use `V2` instead of `DefaultVersion` to talk to newer Cayley servers through `/api/v2/...` endpoints.
+//! They also understand GraphQL queries, send them with `graph.find_graphql(...)`, which fails for V1 graphs.
+//!
+//! No connection is established when you only create a Graph, so this error, if happened,
 //! is not telling that connection was failed here, it just tells about malformed URL.
-//! But things may change, and even when they'll do, you still have a chance to
-//! pattern-match the error, if you need.
+//! Connections are opened with the first requests and then kept alive in a
+//! [pool](./connection/struct.ConnectionPool.html), to be re-used by the next ones.
+//! Requests wait for Cayley as long as it takes, unless you set the timeouts with
+//! [GraphConfig](./graph/struct.GraphConfig.html):
+//!
+//! ```
+//! use std::time::Duration;
+//! use std::default::Default;
+//! use cayley::{Graph, GraphConfig};
+//!
+//! let graph = Graph::default().unwrap()
+//!                   .with_config(GraphConfig { deadline: Some(Duration::seconds(30)),
+//!                                              ..Default::default() });
+//! ```
 //!
 //! ## Query
 //!
@@ -40,21 +61,30 @@
 //!
 //! ```
 //! use cayley::{Graph, DefaultVersion};
-//! use cayley::GraphNodes;
+//! use cayley::{GraphNodes, NodeMaps};
 //! use cayley::path::{Vertex, Query}; // Query trait import is required
 //! use cayley::selector::AnyNode;
 //!
 //! let graph = Graph::new("localhost", 64210, DefaultVersion).unwrap();
 //! match graph.find(Vertex::start(AnyNode).All()) {
-//!    Ok(GraphNodes(nodes)) => assert!(nodes.len() > 0),
+//!    Ok(NodeMaps(GraphNodes(nodes))) => assert!(nodes.len() > 0),
+//!    Ok(_) => panic!("All() should return the tag maps"),
 //!    Err(error) => panic!(error.to_string()),
 //! };
 //! ```
 //!
-//! So in general it looks like `graph.find(<Query>)`.
+//! So in general it looks like `graph.find(<FinalizedQuery>)`, where the query is finalized with `.All()`,
+//! `.GetLimit(...)`, `.ToArray()` or any other [Query](./path/trait.Query.html) finalizer.
 //!
+//! [QueryResult](./graph/enum.QueryResult.html) is an enum which variant depends on the finalizer
+//! the query was ended with: `NodeMaps(...)` for `.All()`, `NodeArray(...)` for `.ToArray()`, ...
 //! [GraphNodes](./graph/struct.GraphNodes.html) is a wrapper for `Vec<GraphNode>`.
-//! [GraphNode](./graph/struct.GraphNode.html) is a wrapper for `HashMap<String, String>`
+//! [GraphNode](./graph/struct.GraphNode.html) is a wrapper for `HashMap<String, String>`,
+//! use `graph.find_as(<FinalizedQuery>)` to [decode](./decode/struct.NodeDecoder.html) every node
+//! into your own structure instead. For the large results, `graph.find_compact(<FinalizedQuery>)`
+//! returns [CompactNodes](./compact/struct.CompactNodes.html) which store every tag name once,
+//! and `graph.find_iter(<FinalizedQuery>)` [reads](./stream/struct.GraphNodesIter.html) the nodes
+//! one by one, while the response is received.
 //!
 //! Morphism used this way:
 //!
@@ -75,17 +105,58 @@
 //!              .All()).unwrap();
 //! ```
 //!
+//! ## MQL
+//!
+//! Queries may also be written as [MQL](./mql/struct.Mql.html) templates:
+//!
+//! ```
+//! use cayley::Graph;
+//! use cayley::mql;
+//! use cayley::mql::Mql;
+//!
+//! let graph = Graph::default().unwrap();
+//! graph.find_mql(&Mql::new(mql::object(vec!(("id", mql::any()),
+//!                                           ("name", mql::value("Casablanca")))))).unwrap();
+//! ```
+//!
+//! ## Writing
+//!
+//! Quads are written and deleted in batches:
+//!
+//! ```
+//! use cayley::{Graph, Quad};
+//!
+//! let graph = Graph::default().unwrap();
+//! let quads = [Quad::new("alice", "follows", "bob"),
+//!              Quad::labeled("bob", "status", "cool_person", "imported")];
+//! graph.write(quads.as_slice()).unwrap();
+//! graph.delete(quads.as_slice()).unwrap();
+//! ```
+//!
+//! Large N-Quads documents are streamed from a file or any `Reader`:
+//!
+//! ```
+//! use cayley::Graph;
+//!
+//! let graph = Graph::default().unwrap();
+//! graph.write_file(&Path::new("./30kmoviedata.nq"),
+//!                  |progress| println!("{} quads sent", progress.quads)).unwrap();
+//! ```
+//!
 //! ## API
 //!
 //! [Gremlin API](https://github.com/google/cayley/blob/master/docs/GremlinAPI.md) is implemented
 //! through these entry points:
 //!
-//! * [Graph](./graph/struct.Graph.html) provides `.find(<Query>)`;
+//! * [Graph](./graph/struct.Graph.html) provides `.find(<FinalizedQuery>)`, `.find_mql(<&Mql>)`, `.find_graphql(<&str>)`, `.shape(<FinalizedQuery>)`, `.write(<&[Quad]>)`, `.delete(<&[Quad]>)`;
 //! * [Vertex](./path/struct.Vertex.html) provides:
 //!     * [Path](./path/trait.Path.html) implemetation with `.Out(...)`, `.In(...)`, `.Has(...)`, `.Or(...)`, `.Follow(...)`, ...
-//!     * [Query](./path/trait.Query.html) implemetation with `.All()`, `.GetLimit(...)`, ...
+//!     * [Query](./path/trait.Query.html) implemetation with `.All()`, `.GetLimit(...)`, `.ToArray()`, `.TagValue()`, ...
 //! * [Morphism](./path/struct.Morphism.html) provides:
 //!     * [Path](./path/trait.Path.html) implemetation with `.Out(...)`, `.In(...)`, `.Has(...)`, `.Or(...)`, `.Follow(...)`, ...
+//! * [Traversal](./step/struct.Traversal.html) is the list of typed steps both of them store, to be
+//!   inspected or rewritten before the query is compiled;
+//! * [parser](./parser/index.html) reads a Gremlin query string back to a Vertex.
 //!
 //! Follow the links above for a complete lists of methods and to get more information
 //! about every mentioned structure.
@@ -99,13 +170,33 @@ extern crate url;
 #[doc(no_inline)]
 extern crate serialize;
 
+extern crate time;
+
 pub use graph::{Graph, GraphNodes, GraphNode};
-pub use graph::{V1, DefaultVersion};
+pub use graph::{QueryResult, NodeMaps, NodeArray, NodeValue, NodeMap, Emitted, NullResult};
+pub use graph::{Quad, WriteResult, Written, Refused};
+pub use upload::UploadProgress;
+pub use graph::{V1, V2, DefaultVersion};
+pub use graph::GraphConfig;
 
 pub mod errors;
 pub mod selector;
 pub mod path;
+pub mod step;
+pub mod parser;
 pub mod graph;
+pub mod decode;
+pub mod compact;
+pub mod stream;
+pub mod mql;
+pub mod shape;
+pub mod render;
+pub mod connection;
+pub mod retry;
+pub mod breaker;
+pub mod upload;
+
+mod events;
 
 // echo "graph.Vertex('Humphrey Bogart').All()" |
 // http --verbose POST localhost:64210/api/v1/query/gremlin Content-Type:text/plain
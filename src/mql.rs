@@ -0,0 +1,78 @@
+use std::collections::TreeMap;
+
+use serialize::json;
+use serialize::json::{Json, Null, List, Object};
+
+use errors::{GraphResult, DecodingFailed};
+
+/// A query in [MQL](https://github.com/google/cayley/blob/master/docs/MQL.md), a JSON template
+/// which Cayley fills with the matching data. `null` placeholder (see `any()`) asks for a single
+/// value, `[]` placeholder (see `all()`) asks for all the values.
+///
+/// Use `graph.find_mql(<&Mql>)` to execute it:
+///
+/// ```
+/// use cayley::Graph;
+/// use cayley::mql;
+/// use cayley::mql::Mql;
+///
+/// let graph = Graph::default().unwrap();
+/// let query = Mql::new(mql::object(vec!(("id", mql::any()),
+///                                       ("name", mql::value("Casablanca")),
+///                                       ("/film/film/starring", mql::all()))));
+/// graph.find_mql(&query).unwrap();
+/// ```
+///
+/// Templates prepared as strings are also supported:
+///
+/// ```
+/// use cayley::mql::Mql;
+///
+/// let query = Mql::from_str("[{\"id\": null, \"name\": \"Casablanca\"}]").unwrap();
+/// ```
+#[deriving(Clone, PartialEq, Show)]
+pub struct Mql {
+    template: Json
+}
+
+impl Mql {
+
+    /// Create an MQL query from a JSON template
+    pub fn new(template: Json) -> Mql { Mql { template: template } }
+
+    /// Create an MQL query from a map of properties, an equivalent of a single JSON object template
+    pub fn from_map(template: TreeMap<String, Json>) -> Mql { Mql::new(Object(template)) }
+
+    /// Parse an MQL query from a JSON template written as a string
+    pub fn from_str(template: &str) -> GraphResult<Mql> {
+        match json::from_str(template) {
+            Err(error) => Err(DecodingFailed(json::ParseError(error), template.to_string())),
+            Ok(parsed) => Ok(Mql::new(parsed))
+        }
+    }
+
+    /// The JSON template of this query
+    pub fn template(&self) -> &Json { &self.template }
+
+    /// The query as it is sent to the server
+    pub fn compile(&self) -> String { self.template.to_string() }
+
+}
+
+/// A placeholder for a single value, `null` in MQL
+pub fn any() -> Json { Null }
+
+/// A placeholder for all the values, `[]` in MQL
+pub fn all() -> Json { List(Vec::new()) }
+
+/// A fixed value to match
+pub fn value(value: &str) -> Json { json::String(value.to_string()) }
+
+/// A nested template object from the list of properties
+pub fn object(properties: Vec<(&str, Json)>) -> Json {
+    let mut object = TreeMap::new();
+    for (name, value) in properties.into_iter() {
+        object.insert(name.to_string(), value);
+    }
+    Object(object)
+}
@@ -0,0 +1,618 @@
+use std::char;
+use std::num;
+use std::fmt::{Show, Formatter, FormatError};
+
+use path::{Vertex, Morphism, FinalizedQuery, Path, Query, Compile, Reuse};
+use selector::{AnyNode, Node, Nodes};
+use step::{Arg, Unset, Single, Multiple, SubQuery};
+use step::{Out, In, Both, Is, Has, As, Back, Save};
+use step::{Finalizer, All, GetLimit, ToArray, ToValue, TagArray, TagValue, ForEach};
+
+/// Parse a finalized Gremlin query, in the subset this crate is able to generate, back to a
+/// [FinalizedQuery](../path/struct.FinalizedQuery.html).
+///
+/// The query may start with morphism declarations (`var name = g.M()...;`) to be followed
+/// with `.Follow(name)` and `.FollowR(name)`, and may be wrapped with `g.Emit(...)`:
+///
+/// ```
+/// use cayley::parser;
+///
+/// let query = "var friends = g.M().Out(\"follows\");g.V(\"C\").Follow(friends).All()";
+/// assert_eq!(parser::parse(query).unwrap().compile().ok(), Some(query.to_string()));
+/// ```
+///
+/// Anything else is reported with the position it was found at:
+///
+/// ```
+/// use cayley::parser;
+///
+/// let error = parser::parse("g.V(\"C\").Skip(5).All()").unwrap_err();
+/// assert_eq!((error.line, error.column), (1, 10));
+/// ```
+pub fn parse(source: &str) -> Result<FinalizedQuery, SyntaxError> {
+    let mut parser = Parser::new(source);
+    try!(parser.declarations());
+    let query = try!(parser.emitted_query());
+    try!(parser.finish());
+    Ok(query)
+}
+
+/// Parse a Gremlin path which is not finalized, optionally preceded with the declarations of the
+/// morphisms it follows, to a [Vertex](../path/struct.Vertex.html).
+pub fn parse_path(source: &str) -> Result<Vertex, SyntaxError> {
+    let mut parser = Parser::new(source);
+    try!(parser.declarations());
+    let (start, method) = try!(parser.graph_method());
+    let vertex = match method.as_slice() {
+        "V" | "Vertex" => try!(parser.vertex()),
+        _ => return parser.fail_at(start, "`g.V(...)` is expected".to_string())
+    };
+    if parser.eat('.') {
+        parser.skip_whitespace();
+        return parser.fail("the path is not expected to be finalized".to_string());
+    }
+    try!(parser.finish());
+    Ok(vertex)
+}
+
+/// Parse a Gremlin morphism (`g.M()...`), optionally preceded with the declarations of the
+/// morphisms it follows, to a [Morphism](../path/struct.Morphism.html) with the given name.
+pub fn parse_morphism(name: &str, source: &str) -> Result<Morphism, SyntaxError> {
+    let mut parser = Parser::new(source);
+    try!(parser.declarations());
+    let (start, method) = try!(parser.graph_method());
+    let morphism = match method.as_slice() {
+        "M" | "Morphism" => try!(parser.morphism(name)),
+        _ => return parser.fail_at(start, "`g.M()` is expected".to_string())
+    };
+    try!(parser.finish());
+    Ok(morphism)
+}
+
+/// An unsupported or malformed piece of a query, found by the [parser](./fn.parse.html)
+#[deriving(Clone, PartialEq)]
+pub struct SyntaxError {
+    /// Position in the source, in characters
+    pub position: uint,
+    /// Line in the source, starting from 1
+    pub line: uint,
+    /// Column in the source, starting from 1
+    pub column: uint,
+    pub message: String
+}
+
+impl Show for SyntaxError {
+
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), FormatError> {
+        write!(fmt, "{} at line {}, column {}", self.message, self.line, self.column)
+    }
+
+}
+
+type ParseResult<T> = Result<T, SyntaxError>;
+
+struct Parser {
+    source: Vec<char>,
+    position: uint,
+    vertices: Vec<(String, Vertex)>,
+    morphisms: Vec<Morphism>
+}
+
+impl Parser {
+
+    fn new(source: &str) -> Parser {
+        Parser { source: source.chars().collect(),
+                 position: 0,
+                 vertices: Vec::new(),
+                 morphisms: Vec::new() }
+    }
+
+    // ----- statements -----
+
+    // `var <name> = <query or morphism>;` statements
+    fn declarations(&mut self) -> ParseResult<()> {
+        loop {
+            self.skip_whitespace();
+            let start = self.position;
+            match self.identifier() {
+                Ok(ref keyword) if keyword.as_slice() == "var" => (),
+                _ => { self.position = start; return Ok(()) }
+            }
+            let name = try!(self.identifier());
+            try!(self.expect('='));
+            let (method_start, method) = try!(self.graph_method());
+            match method.as_slice() {
+                "M" | "Morphism" => {
+                    let morphism = try!(self.morphism(name.as_slice()));
+                    self.morphisms.push(morphism);
+                },
+                "V" | "Vertex" => {
+                    let vertex = try!(self.vertex());
+                    self.vertices.push((name, vertex));
+                },
+                _ => return self.fail_at(method_start, "`g.V(...)` or `g.M()` is expected".to_string())
+            }
+            try!(self.expect(';'));
+        }
+    }
+
+    // the query itself, `g.V(...)...` or `g.Emit(g.V(...)...)`
+    fn emitted_query(&mut self) -> ParseResult<FinalizedQuery> {
+        let (start, method) = try!(self.graph_method());
+        match method.as_slice() {
+            "V" | "Vertex" => self.query(),
+            "Emit" => {
+                try!(self.expect('('));
+                let (inner, method) = try!(self.graph_method());
+                let query = match method.as_slice() {
+                    "V" | "Vertex" => try!(self.query()),
+                    _ => return self.fail_at(inner, "`g.V(...)` is expected".to_string())
+                };
+                try!(self.expect(')'));
+                Ok(query)
+            },
+            _ => self.fail_at(start, "`g.V(...)` is expected".to_string())
+        }
+    }
+
+    fn finish(&mut self) -> ParseResult<()> {
+        self.eat(';');
+        self.skip_whitespace();
+        match self.peek() {
+            None => Ok(()),
+            Some(_) => self.fail("the query is expected to end here".to_string())
+        }
+    }
+
+    // `g.<method>`, returns the name of the method and its position
+    fn graph_method(&mut self) -> ParseResult<(uint, String)> {
+        self.skip_whitespace();
+        let start = self.position;
+        match try!(self.identifier()).as_slice() {
+            "g" | "graph" => (),
+            _ => return self.fail_at(start, "`g` is expected".to_string())
+        }
+        try!(self.expect('.'));
+        self.skip_whitespace();
+        let method_start = self.position;
+        let method = try!(self.identifier());
+        Ok((method_start, method))
+    }
+
+    // ----- paths -----
+
+    // the rest of `g.V(...)...` up to the finalizer, if there is one
+    fn vertex(&mut self) -> ParseResult<Vertex> {
+        let mut vertex = match try!(self.list()) {
+            Unset => Vertex::start(AnyNode),
+            Single(node) => Vertex::start(Node(node.as_slice())),
+            Multiple(nodes) => Vertex::start(Nodes(nodes.iter().map(|node| node.as_slice()).collect())),
+            SubQuery(_) => unreachable!()
+        };
+        loop {
+            let dot = self.position;
+            if !self.eat('.') { break; }
+            self.skip_whitespace();
+            let start = self.position;
+            let method = try!(self.identifier());
+            if is_finalizer(method.as_slice()) {
+                self.position = dot;
+                break;
+            }
+            if !try!(self.path_step(&mut vertex, method.as_slice())) {
+                return self.fail_at(start, format!("unsupported method `{:s}`", method));
+            }
+        }
+        Ok(vertex)
+    }
+
+    // the rest of `g.V(...)...` ended with a finalizer
+    fn query(&mut self) -> ParseResult<FinalizedQuery> {
+        let vertex = try!(self.vertex());
+        try!(self.expect('.'));
+        self.skip_whitespace();
+        let start = self.position;
+        let method = try!(self.identifier());
+        let query = match try!(self.finalizer(method.as_slice())) {
+            Some(finalizer) => vertex.finalize(finalizer),
+            None => return self.fail_at(start, format!("unsupported method `{:s}`", method))
+        };
+        if self.eat('.') {
+            self.skip_whitespace();
+            let start = self.position;
+            let method = try!(self.identifier());
+            return self.fail_at(start, format!("`.{:s}` is called after the query is finalized", method));
+        }
+        Ok(query)
+    }
+
+    // the rest of `g.M()...`
+    fn morphism(&mut self, name: &str) -> ParseResult<Morphism> {
+        let mut morphism = Morphism::start(name);
+        try!(self.expect('('));
+        try!(self.expect(')'));
+        while self.eat('.') {
+            self.skip_whitespace();
+            let start = self.position;
+            let method = try!(self.identifier());
+            if !try!(self.path_step(&mut morphism, method.as_slice())) {
+                return self.fail_at(start, format!("unsupported method `{:s}`", method));
+            }
+        }
+        Ok(morphism)
+    }
+
+    // a Path method call, `false` if there is no such method
+    fn path_step<P: Path>(&mut self, path: &mut P, method: &str) -> ParseResult<bool> {
+        match method {
+            "Out" => { let (first, second) = try!(self.pair()); path.add_step(Out(first, second)); },
+            "In" => { let (first, second) = try!(self.pair()); path.add_step(In(first, second)); },
+            "Both" => { let (first, second) = try!(self.pair()); path.add_step(Both(first, second)); },
+            "Has" => { let (first, second) = try!(self.pair()); path.add_step(Has(first, second)); },
+            "Save" => { let (first, second) = try!(self.pair()); path.add_step(Save(first, second)); },
+            "Is" => { let nodes = try!(self.list()); path.add_step(Is(nodes)); },
+            "As" | "Tag" => { let tags = try!(self.list()); path.add_step(As(tags)); },
+            "Back" => { let tags = try!(self.list()); path.add_step(Back(tags)); },
+            "And" | "Intersect" => {
+                try!(self.expect('('));
+                let query = try!(self.sub_query());
+                try!(self.expect(')'));
+                path.And(&query);
+            },
+            "Or" | "Union" => {
+                try!(self.expect('('));
+                let query = try!(self.sub_query());
+                try!(self.expect(')'));
+                path.Or(&query);
+            },
+            "Follow" => { let morphism = try!(self.followed()); path.Follow(&morphism); },
+            "FollowR" => { let morphism = try!(self.followed()); path.FollowR(&morphism); },
+            _ => return Ok(false)
+        }
+        Ok(true)
+    }
+
+    // a Query finalizer call, `None` if there is no such method
+    fn finalizer(&mut self, method: &str) -> ParseResult<Option<Finalizer>> {
+        Ok(Some(match method {
+            "All" => { try!(self.no_arguments()); All },
+            "GetLimit" => {
+                try!(self.expect('('));
+                let limit = try!(self.integer());
+                try!(self.expect(')'));
+                GetLimit(limit)
+            },
+            "ToArray" => { try!(self.no_arguments()); ToArray },
+            "ToValue" => { try!(self.no_arguments()); ToValue },
+            "TagArray" => { try!(self.no_arguments()); TagArray },
+            "TagValue" => { try!(self.no_arguments()); TagValue },
+            "ForEach" | "Map" => {
+                try!(self.expect('('));
+                self.skip_whitespace();
+                let limit = match self.peek() {
+                    Some(c) if c == '-' || c.is_digit() => {
+                        let limit = try!(self.integer());
+                        try!(self.expect(','));
+                        Some(limit)
+                    },
+                    _ => None
+                };
+                let callback = try!(self.callback());
+                try!(self.expect(')'));
+                ForEach(limit, callback)
+            },
+            _ => return Ok(None)
+        }))
+    }
+
+    // a query passed as an argument, `g.V(...)...` or a name of a declared one
+    fn sub_query(&mut self) -> ParseResult<Vertex> {
+        self.skip_whitespace();
+        let start = self.position;
+        let name = try!(self.identifier());
+        match name.as_slice() {
+            "g" | "graph" => {
+                self.position = start;
+                let (method_start, method) = try!(self.graph_method());
+                let vertex = match method.as_slice() {
+                    "V" | "Vertex" => try!(self.vertex()),
+                    _ => return self.fail_at(method_start, "`g.V(...)` is expected".to_string())
+                };
+                if self.eat('.') {
+                    self.skip_whitespace();
+                    return self.fail("the query passed here is not expected to be finalized".to_string());
+                }
+                Ok(vertex)
+            },
+            _ => match self.vertices.iter().rev().find(|&&(ref declared, _)| *declared == name) {
+                Some(&(_, ref vertex)) => Ok(vertex.clone()),
+                None => self.fail_at(start, format!("`{:s}` is not declared as a query", name))
+            }
+        }
+    }
+
+    // `(<morphism name>)`
+    fn followed(&mut self) -> ParseResult<Morphism> {
+        try!(self.expect('('));
+        self.skip_whitespace();
+        let start = self.position;
+        let name = try!(self.identifier());
+        try!(self.expect(')'));
+        match self.morphisms.iter().rev().find(|morphism| morphism.get_name() == name.as_slice()) {
+            Some(morphism) => Ok(morphism.clone()),
+            None => self.fail_at(start, format!("`{:s}` is not declared as a morphism", name))
+        }
+    }
+
+    // `function(d) { <body> }`, returns the body
+    fn callback(&mut self) -> ParseResult<String> {
+        self.skip_whitespace();
+        let start = self.position;
+        match self.identifier() {
+            Ok(ref keyword) if keyword.as_slice() == "function" => (),
+            _ => return self.fail_at(start, "`function(d) { ... }` is expected".to_string())
+        }
+        try!(self.expect('('));
+        self.skip_whitespace();
+        let argument = self.position;
+        match try!(self.identifier()).as_slice() {
+            "d" => (),
+            _ => return self.fail_at(argument, "the callback argument is expected to be `d`".to_string())
+        }
+        try!(self.expect(')'));
+        try!(self.expect('{'));
+        let body_start = self.position;
+        let mut depth = 1u;
+        while depth > 0 {
+            match self.next() {
+                None => return self.fail_at(body_start, "the callback body is not closed".to_string()),
+                Some('{') => depth += 1,
+                Some('}') => depth -= 1,
+                Some(quote) if quote == '"' || quote == '\'' => {
+                    self.position -= 1;
+                    try!(self.string());
+                },
+                Some(_) => ()
+            }
+        }
+        let mut body = String::new();
+        for c in self.source.slice(body_start, self.position - 1).iter() { body.push(*c); }
+        Ok(body.as_slice().trim().to_string())
+    }
+
+    // ----- arguments -----
+
+    // `()`
+    fn no_arguments(&mut self) -> ParseResult<()> {
+        try!(self.expect('('));
+        self.expect(')')
+    }
+
+    // `("a", "b", ...)`
+    fn list(&mut self) -> ParseResult<Arg> {
+        try!(self.expect('('));
+        let mut values = Vec::new();
+        if !self.eat(')') {
+            loop {
+                values.push(try!(self.string()));
+                if !self.eat(',') { break; }
+            }
+            try!(self.expect(')'));
+        }
+        Ok(match values.len() {
+            0 => Unset,
+            1 => Single(values.pop().unwrap()),
+            _ => Multiple(values)
+        })
+    }
+
+    // `(<predicates>, <tags or nodes>)`, both are optional
+    fn pair(&mut self) -> ParseResult<(Arg, Arg)> {
+        try!(self.expect('('));
+        if self.eat(')') { return Ok((Unset, Unset)); }
+        self.skip_whitespace();
+        let first = match self.peek() {
+            Some(c) if c.is_alphabetic() || c == '_' || c == '$' => {
+                let start = self.position;
+                match try!(self.identifier()).as_slice() {
+                    "null" => Unset,
+                    _ => {
+                        self.position = start;
+                        SubQuery(box try!(self.sub_query()).traversal().clone())
+                    }
+                }
+            },
+            _ => try!(self.value())
+        };
+        let second = match self.eat(',') {
+            true => try!(self.value()),
+            false => Unset
+        };
+        try!(self.expect(')'));
+        Ok((first, second))
+    }
+
+    // `"a"`, `["a", "b", ...]` or `null`
+    fn value(&mut self) -> ParseResult<Arg> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('[') => {
+                self.position += 1;
+                let mut values = Vec::new();
+                if !self.eat(']') {
+                    loop {
+                        values.push(try!(self.string()));
+                        if !self.eat(',') { break; }
+                    }
+                    try!(self.expect(']'));
+                }
+                Ok(Multiple(values))
+            },
+            Some(c) if c.is_alphabetic() => {
+                let start = self.position;
+                match try!(self.identifier()).as_slice() {
+                    "null" => Ok(Unset),
+                    _ => self.fail_at(start, "a string, a list of strings or `null` is expected".to_string())
+                }
+            },
+            _ => Ok(Single(try!(self.string())))
+        }
+    }
+
+    // ----- tokens -----
+
+    fn identifier(&mut self) -> ParseResult<String> {
+        self.skip_whitespace();
+        let mut identifier = String::new();
+        loop {
+            match self.peek() {
+                Some(c) if c.is_alphabetic() || c == '_' || c == '$' ||
+                           (c.is_digit() && !identifier.is_empty()) => {
+                    identifier.push(c);
+                    self.position += 1;
+                },
+                _ => break
+            }
+        }
+        match identifier.is_empty() {
+            true => self.fail("a name is expected".to_string()),
+            false => Ok(identifier)
+        }
+    }
+
+    fn integer(&mut self) -> ParseResult<int> {
+        self.skip_whitespace();
+        let start = self.position;
+        let mut digits = String::new();
+        if self.peek() == Some('-') { digits.push('-'); self.position += 1; }
+        loop {
+            match self.peek() {
+                Some(c) if c.is_digit() => { digits.push(c); self.position += 1; },
+                _ => break
+            }
+        }
+        match from_str::<int>(digits.as_slice()) {
+            Some(number) => Ok(number),
+            None => self.fail_at(start, "an integer is expected".to_string())
+        }
+    }
+
+    // a JavaScript string literal, in double or single quotes
+    fn string(&mut self) -> ParseResult<String> {
+        self.skip_whitespace();
+        let start = self.position;
+        let quote = match self.next() {
+            Some(c) if c == '"' || c == '\'' => c,
+            _ => return self.fail_at(start, "a string is expected".to_string())
+        };
+        let mut value = String::new();
+        loop {
+            match self.next() {
+                None | Some('\n') => return self.fail_at(start, "the string is not closed".to_string()),
+                Some(c) if c == quote => return Ok(value),
+                Some('\\') => {
+                    let escape = self.position - 1;
+                    match self.next() {
+                        Some('n') => value.push('\n'),
+                        Some('r') => value.push('\r'),
+                        Some('t') => value.push('\t'),
+                        Some('b') => value.push('\x08'),
+                        Some('f') => value.push('\x0c'),
+                        Some('0') => value.push('\0'),
+                        Some('u') => {
+                            let mut hex = String::new();
+                            for _ in range(0u, 4) {
+                                match self.next() { Some(c) => hex.push(c), None => () }
+                            }
+                            match num::from_str_radix::<u32>(hex.as_slice(), 16).and_then(char::from_u32) {
+                                Some(c) => value.push(c),
+                                None => return self.fail_at(escape, "invalid unicode escape".to_string())
+                            }
+                        },
+                        Some(c) => value.push(c),
+                        None => return self.fail_at(start, "the string is not closed".to_string())
+                    }
+                },
+                Some(c) => value.push(c)
+            }
+        }
+    }
+
+    // ----- characters -----
+
+    fn peek(&self) -> Option<char> {
+        match self.position < self.source.len() {
+            true => Some(self.source[self.position]),
+            false => None
+        }
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let next = self.peek();
+        if next.is_some() { self.position += 1; }
+        next
+    }
+
+    // skips whitespace and comments
+    fn skip_whitespace(&mut self) {
+        loop {
+            match (self.peek(), self.source.get(self.position + 1).map(|c| *c)) {
+                (Some(c), _) if c.is_whitespace() => self.position += 1,
+                (Some('/'), Some('/')) => {
+                    while self.peek().is_some() && self.peek() != Some('\n') { self.position += 1; }
+                },
+                (Some('/'), Some('*')) => {
+                    self.position += 2;
+                    while self.peek().is_some() &&
+                          !(self.peek() == Some('*') && self.source.get(self.position + 1) == Some(&'/')) {
+                        self.position += 1;
+                    }
+                    self.position = if self.position < self.source.len() { self.position + 2 }
+                                    else { self.source.len() };
+                },
+                _ => return
+            }
+        }
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        self.skip_whitespace();
+        match self.peek() == Some(expected) {
+            true => { self.position += 1; true },
+            false => false
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> ParseResult<()> {
+        match self.eat(expected) {
+            true => Ok(()),
+            false => self.fail(format!("`{}` is expected", expected))
+        }
+    }
+
+    // ----- errors -----
+
+    fn fail<T>(&self, message: String) -> ParseResult<T> {
+        self.fail_at(self.position, message)
+    }
+
+    fn fail_at<T>(&self, position: uint, message: String) -> ParseResult<T> {
+        let mut line = 1u;
+        let mut column = 1u;
+        for c in self.source.slice_to(position).iter() {
+            match *c {
+                '\n' => { line += 1; column = 1; },
+                _ => column += 1
+            }
+        }
+        Err(SyntaxError { position: position, line: line, column: column, message: message })
+    }
+
+}
+
+fn is_finalizer(method: &str) -> bool {
+    match method {
+        "All" | "GetLimit" | "ToArray" | "ToValue" | "TagArray" | "TagValue" | "ForEach" | "Map" => true,
+        _ => false
+    }
+}
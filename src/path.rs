@@ -1,9 +1,13 @@
 use selector::{NodeSelector, TagSelector, PredicateSelector};
 
-use selector::{AnyNode, Node, Nodes};
-use selector::{AnyTag, Tag, Tags};
-use selector::{AnyPredicate, Predicate, Predicates};
-use selector::Query as FromQuery;
+use selector::AnyTag;
+use selector::AnyPredicate;
+
+use errors::GraphResult;
+
+use step::{Traversal, Step, Arg, Finalizer};
+use step::{StartVertex, StartMorphism, Out, In, Both, Is, Has, As, Back, Save, And, Or, Follow, FollowR, Final};
+use step::{All, GetLimit, ToArray, ToValue, TagArray, TagValue, ForEach};
 
 /// An interface to a [Path](../path/trait.Path.html) with the ability to be executed as a [Query](../path/trait.Query.html) to a database.
 /// The main entry point to ask for [GraphNodes](../graph/struct.GraphNodes.html) from database using [Graph](../graph/struct.Graph.html) as an interceptor.
@@ -29,19 +33,21 @@ use selector::Query as FromQuery;
 ///
 /// ```
 /// use cayley::{Graph, DefaultVersion};
-/// use cayley::GraphNodes;
+/// use cayley::{GraphNodes, NodeMaps};
 /// use cayley::path::{Vertex, Query}; // Query trait import is required
 /// use cayley::selector::AnyNode;
 ///
 /// let graph = Graph::new("localhost", 64210, DefaultVersion).unwrap();
 /// match graph.find(Vertex::start(AnyNode).All()) {
-///    Ok(GraphNodes(nodes)) => assert!(nodes.len() > 0),
+///    Ok(NodeMaps(GraphNodes(nodes))) => assert!(nodes.len() > 0),
+///    Ok(_) => panic!("All() should return the tag maps"),
 ///    Err(error) => panic!(error.to_string())
 /// };
 /// ```
 ///
 /// Sometimes it is wanted to separate a vertex instance from a query construction.
-/// Use `prepare` static method for this purpose, but then ensure to start a query with `From` call:
+/// Use `prepare` static method for this purpose, it returns an [UnstartedVertex](../path/struct.UnstartedVertex.html)
+/// which has to be started with `From` call before it could be used:
 ///
 /// ```
 /// #![allow(unused_result)]
@@ -50,17 +56,29 @@ use selector::Query as FromQuery;
 /// use cayley::selector::{Node, Predicate};
 ///
 /// let graph = Graph::default().unwrap();
-/// let mut v = Vertex::prepare();
-/// // NB: Do not finalize the queries you plan to reuse!
-/// v.From(Node("C")).OutP(Predicate("follows"));
-/// let mut other_v = Vertex::prepare();
-/// other_v.From(Node("D")).Union(&v).All();
-/// graph.find(&other_v).unwrap();
+/// let prepared = Vertex::prepare();
+/// let mut v = prepared.From(Node("C"));
+/// v.OutP(Predicate("follows"));
+/// let query = Vertex::prepare().From(Node("D")).Union(&v).All();
+/// graph.find(query).unwrap();
+/// // finalizing copies the path, so `v` may be reused
+/// graph.find(v.All()).unwrap();
 /// ```
 pub struct Vertex {
-    finalized: bool,
-    path: Vec<String>,
-    includes: Option<Vec<String>>
+    traversal: Traversal
+}
+
+/// A [Vertex](../path/struct.Vertex.html) prepared with `Vertex::prepare()`, the only thing it
+/// is able to do is to start a path with `.From(<NodeSelector>)`.
+pub struct UnstartedVertex;
+
+/// A query finalized with one of the [Query](../path/trait.Query.html) methods, like `.All()` or
+/// `.GetLimit(<n>)`. It is the only thing [Graph](../graph/struct.Graph.html) is able to execute,
+/// and no steps could be added to it anymore.
+#[deriving(Clone)]
+pub struct FinalizedQuery {
+    traversal: Traversal,
+    finalizer: Finalizer
 }
 
 /// An interface to a [Path](../path/trait.Path.html) with the ability to be saved and reused to
@@ -87,8 +105,7 @@ pub struct Vertex {
 pub struct Morphism {
     saved: bool,
     name: String,
-    path: Vec<String>,
-    includes: Option<Vec<String>>
+    traversal: Traversal
 }
 
 // ================================ Compile ================================= //
@@ -96,22 +113,21 @@ pub struct Morphism {
 /// Marks any Path which is able to be compiled to a string Gremlin-compatible query
 pub trait Compile: Clone/*+ToString*/ {
 
-    fn add_str(&mut self, what: &str) -> &mut Self;
-
-    fn add_string(&mut self, what: String) -> &mut Self;
+    /// The steps of this path, see [Traversal](../step/struct.Traversal.html)
+    fn traversal(&self) -> &Traversal;
 
-    fn compile(&self) -> Option<String>;
+    /// The steps of this path, to be modified in place
+    fn traversal_mut(&mut self) -> &mut Traversal;
 
-    /* fn to_string(&self) -> String {
-        match self.compile() {
-            Some(compiled) => compiled,
-            None => "[-]".to_string()
-        }
+    /// Add the step to this path, the mistakes are recorded and reported by `.compile()`
+    fn add_step(&mut self, step: Step) -> &mut Self {
+        self.traversal_mut().push(step);
+        self
     }
 
-    fn fmt(&self, fmt: &mut Formatter) -> Result<(), FormatError> {
-        write!(fmt, "{}", self.to_string())
-    } */
+    /// Compile this path to a query string, fails with `QueryBuildFailed` holding all the
+    /// [errors](../errors/enum.BuildError.html) made while building it
+    fn compile(&self) -> GraphResult<String> { self.traversal().compile() }
 
 }
 
@@ -162,9 +178,10 @@ pub trait Compile: Clone/*+ToString*/ {
 ///
 /// For `.Intersect`, `.And`, `.Union`, `.Or` methods, using `.Intersect` as example:
 ///
-/// * `let some_v = Vertex(AnyNode).OutT(Tag("follows")).All();`
+/// * `let some_v = Vertex::start(AnyNode).OutT(Tag("follows"));`
 ///   `graph.find(Vertex::start(Node("C")).Intersect(&some_v).All());`
-///    is equivalent to Gremlin `g.V("C").Intersect(g.V.Out("follows").All()).All();`;
+///    is equivalent to Gremlin `g.V("C").Intersect(g.V().Out(null, "follows")).All();`
+///    (the query passed can not be finalized);
 ///
 /// For `Follow` and `FollowR` methods:
 ///
@@ -179,7 +196,7 @@ pub trait Path: Compile {
 
     /// `.Out` Path method. Follow forwards the quads with given predicates.
     fn Out(&mut self, predicates: PredicateSelector, tags: TagSelector) -> &mut Self {
-        self.add_string(format!("Out({:s})", predicates_and_tags(predicates, tags)))
+        self.add_step(Out(Arg::from_predicates(predicates), Arg::from_tags(tags)))
     }
 
     // ---------------------------------- OutP ---------------------------------
@@ -200,7 +217,7 @@ pub trait Path: Compile {
 
     /// `.In` Path method. Follow backwards the quads with given predicates.
     fn In(&mut self, predicates: PredicateSelector, tags: TagSelector) -> &mut Self {
-        self.add_string(format!("In({:s})", predicates_and_tags(predicates, tags)))
+        self.add_step(In(Arg::from_predicates(predicates), Arg::from_tags(tags)))
     }
 
     // ---------------------------------- InP ----------------------------------
@@ -221,7 +238,7 @@ pub trait Path: Compile {
 
     /// `.Both` Path method.
     fn Both(&mut self, predicates: PredicateSelector, tags: TagSelector) -> &mut Self {
-        self.add_string(format!("Both({:s})", predicates_and_tags(predicates, tags)))
+        self.add_step(Both(Arg::from_predicates(predicates), Arg::from_tags(tags)))
     }
 
     // ---------------------------------- BothP --------------------------------
@@ -242,18 +259,14 @@ pub trait Path: Compile {
 
     /// `.Is` Path method. Filter all paths which are on the given node(-s).
     fn Is(&mut self, nodes: NodeSelector) -> &mut Self {
-        self.add_string(match nodes {
-            AnyNode/*| Node("") */ => "Is()".to_string(),
-            Node(name) => format!("Is(\"{:s}\")", name),
-            Nodes(names) => format!("Is(\"{:s}\")", names.connect("\",\""))
-        })
+        self.add_step(Is(Arg::from_nodes(nodes)))
     }
 
     // ---------------------------------- Has ----------------------------------
 
     /// `.Has` Path method. Filter all paths which are on the subject, but do not follow the path.
     fn Has(&mut self, predicates: PredicateSelector, nodes: NodeSelector) -> &mut Self {
-        self.add_string(format!("Has({:s})", predicates_and_nodes(predicates, nodes)))
+        self.add_step(Has(Arg::from_predicates(predicates), Arg::from_nodes(nodes)))
     }
 
     // ---------------------------------- Tag ----------------------------------
@@ -265,29 +278,21 @@ pub trait Path: Compile {
 
     /// `.As` Path method. Mark items with a tag.
     fn As(&mut self, tags: TagSelector) -> &mut Self {
-        self.add_string(match tags {
-            AnyTag/*| Node("") */ => "As()".to_string(),
-            Tag(name) => format!("As(\"{:s}\")", name),
-            Tags(names) => format!("As(\"{:s}\")", names.connect("\",\""))
-        })
+        self.add_step(As(Arg::from_tags(tags)))
     }
 
     // ---------------------------------- Back ---------------------------------
 
     /// `.Back` Path method. Follow backwards the tagged quads.
     fn Back(&mut self, tags: TagSelector) -> &mut Self {
-        self.add_string(match tags {
-            AnyTag/*| Node("") */ => "Back()".to_string(),
-            Tag(name) => format!("Back(\"{:s}\")", name),
-            Tags(names) => format!("Back(\"{:s}\")", names.connect("\",\""))
-        })
+        self.add_step(Back(Arg::from_tags(tags)))
     }
 
     // ---------------------------------- Save ---------------------------------
 
     /// `.Save` Path method. Save all quads with predicate into tag, without traversal.
     fn Save(&mut self, predicates: PredicateSelector, tags: TagSelector) -> &mut Self {
-        self.add_string(format!("Save({:s})", predicates_and_tags(predicates, tags)))
+        self.add_step(Save(Arg::from_predicates(predicates), Arg::from_tags(tags)))
     }
 
     // ---------------------------------- Intersect ----------------------------
@@ -299,12 +304,7 @@ pub trait Path: Compile {
 
     /// `.And` Path method. Intersect the results from one query with another.
     fn And(&mut self, query: &Query) -> &mut Self {
-        /* FIXME: implicit return looking not so good here? */
-        match query.compile() {
-            Some(compiled) => { return self.add_string(format!("And({:s})", compiled)); },
-            None => { } /* FIXME: save error */
-        }
-        self
+        self.add_step(And(box query.traversal().clone()))
     }
 
     // ---------------------------------- Union --------------------------------
@@ -316,58 +316,126 @@ pub trait Path: Compile {
 
     /// `.Or` Path method. Join the results from one query with another.
     fn Or(&mut self, query: &Query) -> &mut Self {
-        /* FIXME: implicit return looking not so good here? */
-        match query.compile() {
-            Some(compiled) => { return self.add_string(format!("Or({:s})", compiled)); },
-            None => { } /* FIXME: save error */
-        }
-        self
+        self.add_step(Or(box query.traversal().clone()))
     }
 
     // ---------------------------------- Follow -------------------------------
 
     /// `.Follow` Path method. Applies the path chain on the `Morphism` object to the current path.
     fn Follow(&mut self, reusable: &Reuse) -> &mut Self {
-        self.add_string(format!("Follow({:s})", reusable.get_name()))
+        self.traversal_mut().include(reusable.get_name(), reusable.traversal());
+        self.add_step(Follow(reusable.get_name().to_string()))
     }
 
     // ---------------------------------- FollowR ------------------------------
 
     /// `.FollowR` Path method. Applies the path chain on the `Morphism` object to the current path.
     fn FollowR(&mut self, reusable: &Reuse) -> &mut Self {
-        self.add_string(format!("FollowR({:s})", reusable.get_name()))
+        self.traversal_mut().include(reusable.get_name(), reusable.traversal());
+        self.add_step(FollowR(reusable.get_name().to_string()))
     }
 
 }
 
 // ================================ Query =================================== //
 
+/// The trait which covers the finalizing methods from [Gremlin API](https://github.com/google/cayley/blob/master/docs/GremlinAPI.md)
+/// Query specification. Every finalizer defines the shape of the result returned by `graph.find(...)`:
+///
+/// * `.All()`, `.GetLimit(<n>)` and `.TagArray()` return [NodeMaps](../graph/enum.QueryResult.html),
+///   the tag maps of every item found;
+/// * `.ToArray()` returns [NodeArray](../graph/enum.QueryResult.html), the ids of every item found;
+/// * `.ToValue()` returns [NodeValue](../graph/enum.QueryResult.html), the id of the first item found;
+/// * `.TagValue()` returns [NodeMap](../graph/enum.QueryResult.html), the tag map of the first item found;
+/// * `.ForEach(<callback>)` returns [Emitted](../graph/enum.QueryResult.html), every value the callback
+///   passed to `g.Emit(...)`;
+/// * any of them returns [NullResult](../graph/enum.QueryResult.html) if Cayley replied with `null`.
+///
+/// Finalizers return a [FinalizedQuery](../path/struct.FinalizedQuery.html), the only thing `graph.find(...)`
+/// accepts, so a query can neither be executed without a finalizer nor extended after it.
 #[allow(non_snake_case)]
 pub trait Query: Path {
 
-    fn set_finalized(&mut self);
-
-    fn is_finalized(&self) -> bool;
+    /// Finalize a copy of this path with the given finalizer, the path itself stays unchanged
+    /// and may be extended or finalized again
+    fn finalize(&self, finalizer: Finalizer) -> FinalizedQuery {
+        FinalizedQuery::new(self.traversal(), finalizer)
+    }
 
     // ---------------------------------- All ----------------------------------
 
     /// `.All` Query method. Equivalent to Gremlin `Query.All()`.
     /// Returns all the items found within this path.
-    fn All(&mut self) -> &mut Self { self.set_finalized(); self.add_str("All()") }
+    fn All(&self) -> FinalizedQuery { self.finalize(All) }
 
     // ---------------------------------- GetAll -------------------------------
 
     /// `.GetLimit` Query method. Equivalent to Gremlin `Query.GetLimit(<number>)`.
     /// Returns first `<n>` items found within this path.
-    fn GetLimit(&mut self, limit: int) -> &mut Self {
-        self.set_finalized(); self.add_string(format!("GetLimit({:i})", limit))
+    fn GetLimit(&self, limit: int) -> FinalizedQuery { self.finalize(GetLimit(limit)) }
+
+    // ---------------------------------- ToArray ------------------------------
+
+    /// `.ToArray` Query method. Equivalent to Gremlin `Query.ToArray()`.
+    /// Returns the ids of all the items found within this path.
+    fn ToArray(&self) -> FinalizedQuery { self.finalize(ToArray) }
+
+    // ---------------------------------- ToValue ------------------------------
+
+    /// `.ToValue` Query method. Equivalent to Gremlin `Query.ToValue()`.
+    /// Returns the id of the first item found within this path.
+    fn ToValue(&self) -> FinalizedQuery { self.finalize(ToValue) }
+
+    // ---------------------------------- TagArray -----------------------------
+
+    /// `.TagArray` Query method. Equivalent to Gremlin `Query.TagArray()`.
+    /// Returns the tag maps of all the items found within this path.
+    fn TagArray(&self) -> FinalizedQuery { self.finalize(TagArray) }
+
+    // ---------------------------------- TagValue -----------------------------
+
+    /// `.TagValue` Query method. Equivalent to Gremlin `Query.TagValue()`.
+    /// Returns the tag map of the first item found within this path.
+    fn TagValue(&self) -> FinalizedQuery { self.finalize(TagValue) }
+
+    // ---------------------------------- ForEach ------------------------------
+
+    /// `.ForEach` Query method. Equivalent to Gremlin `Query.ForEach(function(d) { <callback> })`.
+    /// Calls the callback for every item found within this path, where `d` is the tag map of
+    /// the item. The callback is the JavaScript body of the function and is expected to pass
+    /// the values to return with `g.Emit(...)`:
+    ///
+    /// ```
+    /// use cayley::Graph;
+    /// use cayley::path::{Vertex, Query};
+    /// use cayley::selector::Node;
+    ///
+    /// let graph = Graph::default().unwrap();
+    /// graph.find(Vertex::start(Node("Casablanca")).ForEach("g.Emit(d.id)")).unwrap();
+    /// ```
+    fn ForEach(&self, callback: &str) -> FinalizedQuery {
+        self.finalize(ForEach(None, callback.to_string()))
     }
 
-    /* TODO: ToArray() */
-    /* TODO: ToValue() */
-    /* TODO: TagArray() */
-    /* TODO: TagValue() */
-    /* TODO: query.ForEach(callback), query.ForEach(limit, callback) */
+    // ---------------------------------- ForEachLimit -------------------------
+
+    /// `.ForEachLimit` Query method. Equivalent to Gremlin `Query.ForEach(<limit>, function(d) { <callback> })`.
+    /// Same as `.ForEach`, but calls the callback only for first `<limit>` items found within this path.
+    fn ForEachLimit(&self, limit: int, callback: &str) -> FinalizedQuery {
+        self.finalize(ForEach(Some(limit), callback.to_string()))
+    }
+
+    // ---------------------------------- Map ----------------------------------
+
+    /// `.Map`, an alias for `.ForEach`
+    fn Map(&self, callback: &str) -> FinalizedQuery { self.ForEach(callback) }
+
+    // ---------------------------------- MapLimit -----------------------------
+
+    /// `.MapLimit`, an alias for `.ForEachLimit`
+    fn MapLimit(&self, limit: int, callback: &str) -> FinalizedQuery {
+        self.ForEachLimit(limit, callback)
+    }
 
 }
 
@@ -378,100 +446,66 @@ impl Vertex {
 
     /// Create a Vertex instance and start a query from [NodeSelector](../selector/struct.NodeSelector.html)
     pub fn start(nodes: NodeSelector) -> Vertex {
-        let mut res = Vertex::prepare();
-        res.From(nodes);
-        res
+        Vertex::prepare().From(nodes)
     }
 
-    /// Prepare a vertex instance to specify a query later. Ensure to start a query with `.From()` method
-    /// if you use `prepare()`.
-    pub fn prepare() -> Vertex {
-        /* FIXME: calling this with no From call afterwars should fail the construction */
-        Vertex{ path: Vec::with_capacity(10), includes: None, finalized: false }
-    }
+    /// Prepare a vertex instance to specify a query later, it has to be started with `.From()` method
+    /// before any other method is available.
+    pub fn prepare() -> UnstartedVertex { UnstartedVertex }
 
-    /// A method for postponed query creation, intended to be used after the `prepare()` method
-    /// on the same Vertex instance.
-    pub fn From(&mut self, nodes: NodeSelector) -> &mut Vertex {
-        match self.path.is_empty() {
-            true => (),
-            false => panic!("Vertex.From should be the first method to be called after Vertex::prepare()
-                           or Vertex::start(nodes) should be used instead")
-        }
-        self.add_str("g");
-        self.add_string(match nodes {
-                Nodes(names) => format!("V(\"{:s}\")", names.connect("\",\"")),
-                Node(name) => format!("V(\"{:s}\")", name),
-                AnyNode/*| Node("") */ => "V()".to_string()
-            })
-    }
+}
 
-    fn add_include(&mut self, include: &Reuse) {
-        match include.save() {
-            Some(saved) =>
-                match self.includes {
-                    Some(ref mut includes) => includes.push(saved),
-                    None => self.includes = Some(vec![saved])
-                },
-            None => { }
-        }
+#[allow(non_snake_case)]
+impl UnstartedVertex {
+
+    /// Start the query prepared with `Vertex::prepare()` from [NodeSelector](../selector/struct.NodeSelector.html)
+    pub fn From(self, nodes: NodeSelector) -> Vertex {
+        let mut res = Vertex { traversal: Traversal::new() };
+        res.add_step(StartVertex(Arg::from_nodes(nodes)));
+        res
     }
 
 }
 
 impl Compile for Vertex {
 
-    fn add_str(&mut self, str: &str) -> &mut Vertex {
-        self.path.push(str.to_string());
-        self
-    }
+    fn traversal(&self) -> &Traversal { &self.traversal }
 
-    fn add_string(&mut self, str: String) -> &mut Vertex {
-        self.path.push(str);
-        self
-    }
-
-    fn compile(&self) -> Option<String> {
-        Some(
-            match self.includes {
-                None => self.path.connect("."),
-                Some(ref includes) => includes.connect(";") + ";".to_string() + self.path.connect(".")
-            })
-    }
+    fn traversal_mut(&mut self) -> &mut Traversal { &mut self.traversal }
 
 }
 
-impl Path for Vertex {
+impl Path for Vertex { }
 
-    fn Follow(&mut self, reusable: &Reuse) -> &mut Vertex {
-        self.add_include(reusable);
-        //Path::Follow(self, reusable)
-        self.add_string(format!("Follow({:s})", reusable.get_name()))
-    }
+impl Query for Vertex { }
+
+impl Clone for Vertex {
 
-    fn FollowR(&mut self, reusable: &Reuse) -> &mut Vertex {
-        self.add_include(reusable);
-        //Path::FollowR(self, reusable)
-        self.add_string(format!("FollowR({:s})", reusable.get_name()))
+    fn clone(&self) -> Vertex {
+        Vertex { traversal: self.traversal.clone() }
     }
 
 }
 
-impl Query for Vertex {
+// ================================ FinalizedQuery ========================== //
 
-    fn set_finalized(&mut self) { self.finalized = true; }
+impl FinalizedQuery {
 
-    fn is_finalized(&self) -> bool { self.finalized }
+    fn new(path: &Traversal, finalizer: Finalizer) -> FinalizedQuery {
+        let mut traversal = path.clone();
+        traversal.push(Final(finalizer.clone()));
+        FinalizedQuery { traversal: traversal, finalizer: finalizer }
+    }
 
-}
+    /// The finalizer this query was finalized with, it defines the shape of the result; if
+    /// the path was already finalized with a raw step, the query fails to compile instead
+    pub fn finalizer(&self) -> &Finalizer { &self.finalizer }
 
-impl Clone for Vertex {
+    /// The steps of this query, see [Traversal](../step/struct.Traversal.html)
+    pub fn traversal(&self) -> &Traversal { &self.traversal }
 
-    fn clone(&self) -> Vertex {
-        Vertex { finalized: self.finalized,
-                 path: self.path.clone(),
-                 includes: self.includes.clone() }
-    }
+    /// Compile this query to a string Gremlin-compatible query
+    pub fn compile(&self) -> GraphResult<String> { self.traversal.compile() }
 
 }
 
@@ -487,18 +521,14 @@ pub trait Reuse: Compile {
     /// Was this item saved at least once in _some_ graph during this session.
     /*pub*/ fn is_saved(&self) -> bool;
 
-    fn save(&self) -> Option<String> {
-        match self.compile() {
-            Some(compiled) => Some(format!("var {:s} = {:s}", self.get_name(), compiled)),
-            None => None
-        }
+    fn save(&self) -> GraphResult<String> {
+        self.traversal().compile_as(self.get_name())
     }
 
-    fn save_as(&self, name: &str) -> Option<String> {
-        match self.compile() {
-            Some(compiled) => Some(format!("var {:s} = {:s}", name, compiled)),
-            None => None
-        }
+    fn save_as(&self, name: &str) -> GraphResult<String> {
+        let mut traversal = self.traversal().clone();
+        traversal.check_name(name);
+        traversal.compile_as(name)
     }
 
 }
@@ -507,66 +537,29 @@ pub trait Reuse: Compile {
 
 impl Morphism {
 
-    /// Create a Morphism instance with intention to store it in database under the given name
+    /// Create a Morphism instance with intention to store it in database under the given name,
+    /// the name should be a valid JavaScript identifier, otherwise `InvalidName` error is
+    /// reported when the morphism, or the path following it, is compiled
     pub fn start(name: &str) -> Morphism {
         let mut res = Morphism { name: name.to_string(),
-                                 path: Vec::with_capacity(10),
-                                 includes: None,
+                                 traversal: Traversal::new(),
                                  saved: false };
-        res.add_string("g.M()".to_string());
+        res.traversal.check_name(name);
+        res.add_step(StartMorphism);
         res
     }
 
-    fn add_include(&mut self, include: &Reuse) {
-        match include.save() {
-            Some(saved) =>
-                match self.includes {
-                    Some(ref mut includes) => includes.push(saved),
-                    None => self.includes = Some(vec![saved])
-                },
-            None => { }
-        }
-    }
-
 }
 
 impl Compile for Morphism {
 
-    fn add_str(&mut self, str: &str) -> &mut Morphism {
-        self.path.push(str.to_string());
-        self
-    }
-
-    fn add_string(&mut self, str: String) -> &mut Morphism {
-        self.path.push(str);
-        self
-    }
+    fn traversal(&self) -> &Traversal { &self.traversal }
 
-    fn compile(&self) -> Option<String> {
-        Some(
-            match self.includes {
-                None => self.path.connect("."),
-                Some(ref includes) => includes.connect(";") + ";".to_string() + self.path.connect(".")
-            })
-    }
+    fn traversal_mut(&mut self) -> &mut Traversal { &mut self.traversal }
 
 }
 
-impl Path for Morphism {
-
-    fn Follow(&mut self, reusable: &Reuse) -> &mut Morphism {
-        self.add_include(reusable);
-        //Path::Follow(self, reusable)
-        self.add_string(format!("Follow({:s})", reusable.get_name()))
-    }
-
-    fn FollowR(&mut self, reusable: &Reuse) -> &mut Morphism {
-        self.add_include(reusable);
-        //Path::FollowR(reusable)
-        self.add_string(format!("FollowR({:s})", reusable.get_name()))
-    }
-
-}
+impl Path for Morphism { }
 
 impl Reuse for Morphism {
 
@@ -583,96 +576,7 @@ impl Clone for Morphism {
     fn clone(&self) -> Morphism {
         Morphism { saved: self.saved,
                    name: self.name.clone(),
-                   path: self.path.clone(),
-                   includes: self.includes.clone() }
+                   traversal: self.traversal.clone() }
     }
 
 }
-
-// ================================ utils =================================== //
-
-fn predicates_and_tags(predicates: PredicateSelector, tags: TagSelector) -> String {
-    match (predicates, tags) {
-
-        (AnyPredicate, AnyTag) => "".to_string(),
-        (AnyPredicate, Tag(tag)) => format!("null,\"{:s}\"", tag),
-        (AnyPredicate, Tags(tags)) => format!("null,[\"{:s}\"]", tags.connect("\",\"")),
-
-        (Predicate(predicate), AnyTag) => format!("\"{:s}\"", predicate),
-        (Predicate(predicate), Tag(tag)) =>
-            format!("\"{:s}\",\"{:s}\"", predicate, tag),
-        (Predicate(predicate), Tags(tags)) =>
-            format!("\"{:s}\",[\"{:s}\"]", predicate, tags.connect("\",\"")),
-
-        (Predicates(predicates), AnyTag) =>
-            format!("[\"{:s}\"]", predicates.connect("\",\"")),
-        (Predicates(predicates), Tag(tag)) =>
-            format!("[\"{:s}\"],\"{:s}\"", predicates.connect("\",\""), tag),
-        (Predicates(predicates), Tags(tags)) =>
-            format!("[\"{:s}\"],[\"{:s}\"]", predicates.connect("\",\""), tags.connect("\",\"")),
-
-        (FromQuery(query), AnyTag) =>
-            match query.compile() {
-                Some(compiled) => compiled,
-                None => "null".to_string()
-            },
-        (FromQuery(query), Tag(tag)) =>
-            format!("{:s}, \"{:s}\"",
-                    match query.compile() {
-                        Some(compiled) => compiled,
-                        None => "null".to_string()
-                    },
-                    tag),
-        (FromQuery(query), Tags(tags)) =>
-            format!("{:s}, [\"{:s}\"]",
-                    match query.compile() {
-                        Some(compiled) => compiled,
-                        None => "null".to_string()
-                    },
-                    tags.connect("\",\""))
-
-    }
-}
-
-fn predicates_and_nodes(predicates: PredicateSelector, nodes: NodeSelector) -> String {
-    match (predicates, nodes) {
-
-        (AnyPredicate, AnyNode) => "".to_string(),
-        (AnyPredicate, Node(node)) => format!("null,\"{:s}\"", node),
-        (AnyPredicate, Nodes(nodes)) => format!("null,[\"{:s}\"]", nodes.connect("\",\"")),
-
-        (Predicate(predicate), AnyNode) => format!("\"{:s}\"", predicate),
-        (Predicate(predicate), Node(tag)) =>
-            format!("\"{:s}\",\"{:s}\"", predicate, tag),
-        (Predicate(predicate), Nodes(nodes)) =>
-            format!("\"{:s}\",[\"{:s}\"]", predicate, nodes.connect("\",\"")),
-
-        (Predicates(predicates), AnyNode) =>
-            format!("[\"{:s}\"]", predicates.connect("\",\"")),
-        (Predicates(predicates), Node(node)) =>
-            format!("[\"{:s}\"],\"{:s}\"", predicates.connect("\",\""), node),
-        (Predicates(predicates), Nodes(nodes)) =>
-            format!("[\"{:s}\"],[\"{:s}\"]", predicates.connect("\",\""), nodes.connect("\",\"")),
-
-        (FromQuery(query), AnyNode) =>
-            match query.compile() {
-                Some(compiled) => compiled,
-                None => "null".to_string()
-            },
-        (FromQuery(query), Node(node)) =>
-            format!("{:s},\"{:s}\"",
-                    match query.compile() {
-                        Some(compiled) => compiled,
-                        None => "null".to_string()
-                    },
-                    node),
-        (FromQuery(query), Nodes(nodes)) =>
-            format!("{:s},[\"{:s}\"]",
-                    match query.compile() {
-                        Some(compiled) => compiled,
-                        None => "null".to_string()
-                    },
-                    nodes.connect("\",\""))
-
-    }
-}
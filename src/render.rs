@@ -0,0 +1,142 @@
+use std::collections::{HashMap, HashSet};
+
+use shape::{Shape, ShapeNode, ShapeLink};
+
+/// Render the [Shape](../shape/struct.Shape.html) as a [Graphviz](http://www.graphviz.org/) DOT document.
+///
+/// Fixed nodes are drawn as boxes, link nodes as diamonds, and the nodes holding the predicates
+/// are joined to the link nodes they are used in with dashed edges.
+///
+/// ```
+/// use cayley::graph::Graph;
+/// use cayley::path::{Vertex, Path, Query};
+/// use cayley::selector::{Predicate, Node};
+/// use cayley::render;
+///
+/// let graph = Graph::default().unwrap();
+/// let shape = graph.shape(Vertex::start(Node("foo")).InP(Predicate("bar")).All()).unwrap();
+/// println!("{}", render::to_dot(&shape));
+/// ```
+pub fn to_dot(shape: &Shape) -> String {
+    let mut output = "digraph shape {\n".to_string();
+    for node in shape.nodes.iter() {
+        let mut label = format!("#{:d}", node.id);
+        if !node.values.is_empty() {
+            label.push_str("\\n");
+            label.push_str(escape(node.values.connect(", ").as_slice()).as_slice());
+        }
+        if !node.tags.is_empty() {
+            label.push_str("\\ntags: ");
+            label.push_str(escape(node.tags.connect(", ").as_slice()).as_slice());
+        }
+        let form = if node.is_link_node { "diamond" } else if node.is_fixed { "box" } else { "ellipse" };
+        output.push_str(format!("    n{:d} [label=\"{:s}\", shape={:s}];\n",
+                                node.id, label, form).as_slice());
+    }
+    for link in shape.links.iter() {
+        output.push_str(format!("    n{:d} -> n{:d};\n", link.source, link.link_node).as_slice());
+        output.push_str(format!("    n{:d} -> n{:d};\n", link.link_node, link.target).as_slice());
+        output.push_str(format!("    n{:d} -> n{:d} [style=dashed, label=\"predicate\"];\n",
+                                link.predicate, link.link_node).as_slice());
+    }
+    output.push_str("}\n");
+    output
+}
+
+/// Render the [Shape](../shape/struct.Shape.html) as an indented tree, to be printed in terminal.
+///
+/// Every branch is a link, labeled with the values of its predicate, and the nodes which are
+/// reachable in several ways are expanded only once:
+///
+/// ```text
+/// #0 fixed ["Casablanca"]
+/// `-- [name] #3 tags: ["movie"]
+/// ```
+pub fn to_tree(shape: &Shape) -> String {
+    let nodes: HashMap<int, &ShapeNode> = shape.nodes.iter().map(|node| (node.id, node)).collect();
+    let mut output = String::new();
+    let mut visited: HashSet<int> = HashSet::new();
+    for root in roots(shape).iter() {
+        visited.insert(*root);
+        output.push_str(describe(&nodes, *root).as_slice());
+        output.push('\n');
+        write_children(shape, &nodes, *root, "", &mut visited, &mut output);
+    }
+    output
+}
+
+// nodes which are neither reached by any link, nor serve links themselves
+fn roots(shape: &Shape) -> Vec<int> {
+    let roots: Vec<int> = shape.nodes.iter().filter(|node| {
+        !node.is_link_node &&
+        !shape.links.iter().any(|link| link.target == node.id || link.predicate == node.id)
+    }).map(|node| node.id).collect();
+    match (roots.is_empty(), shape.nodes.iter().next()) {
+        (true, Some(first)) => vec!(first.id), // the shape is a cycle
+        _ => roots
+    }
+}
+
+fn write_children(shape: &Shape, nodes: &HashMap<int, &ShapeNode>, id: int, prefix: &str,
+                  visited: &mut HashSet<int>, output: &mut String) {
+    let links: Vec<&ShapeLink> = shape.links.iter().filter(|link| link.source == id).collect();
+    let count = links.len();
+    for (index, link) in links.iter().enumerate() {
+        let last = index + 1 == count;
+        output.push_str(prefix);
+        output.push_str(if last { "`-- " } else { "+-- " });
+        output.push_str(format!("[{:s}] ", predicate_label(nodes, link.predicate)).as_slice());
+        output.push_str(describe(nodes, link.target).as_slice());
+        if !visited.insert(link.target) {
+            output.push_str(" (see above)\n");
+            continue;
+        }
+        output.push('\n');
+        let child_prefix = format!("{:s}{:s}", prefix, if last { "    " } else { "|   " });
+        write_children(shape, nodes, link.target, child_prefix.as_slice(), visited, output);
+    }
+}
+
+fn describe(nodes: &HashMap<int, &ShapeNode>, id: int) -> String {
+    match nodes.find(&id) {
+        None => format!("#{:d} (missing)", id),
+        Some(node) => {
+            let mut description = format!("#{:d}", id);
+            if node.is_link_node { description.push_str(" link"); }
+            if node.is_fixed { description.push_str(" fixed"); }
+            if !node.values.is_empty() {
+                description.push_str(format!(" {:s}", quoted(&node.values)).as_slice());
+            }
+            if !node.tags.is_empty() {
+                description.push_str(format!(" tags: {:s}", quoted(&node.tags)).as_slice());
+            }
+            description
+        }
+    }
+}
+
+fn predicate_label(nodes: &HashMap<int, &ShapeNode>, id: int) -> String {
+    match nodes.find(&id) {
+        Some(node) if !node.values.is_empty() => node.values.connect(", "),
+        _ => format!("#{:d}", id)
+    }
+}
+
+fn quoted(items: &Vec<String>) -> String {
+    let items: Vec<String> = items.iter().map(|item| format!("\"{:s}\"", item)).collect();
+    format!("[{:s}]", items.connect(", "))
+}
+
+// escape a text to be placed inside a double-quoted DOT string
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            _ => escaped.push(c)
+        }
+    }
+    escaped
+}
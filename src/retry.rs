@@ -0,0 +1,116 @@
+use std::rand;
+use std::cmp;
+use std::default::Default;
+use std::io::timer;
+use std::time::Duration;
+
+use errors::{GraphResult, RetriesExhausted};
+
+/// Tells how the failed requests are repeated, pass it to `graph.with_retry(...)`
+///
+/// Only the requests which do not change the data (`find...`, `exec...`, `shape`) are repeated,
+/// and the writes only when they are explicitly sent with `write_idempotent(...)` or
+/// `delete_idempotent(...)`; the others are not even resent through a new connection when
+/// the pooled one turns out to be closed. A request is repeated when it failed with the error
+/// which [is_retryable](../errors/enum.GraphRequestError.html#method.is_retryable).
+///
+/// Before every next attempt the policy waits for the backoff, which starts at `initial_backoff`
+/// and is multiplied by `multiplier` after every attempt, up to `max_backoff`. With `jitter`,
+/// a random part of it (up to a half) is cut, so the clients which failed together do not
+/// come back together.
+///
+/// When the request was repeated, the last error is returned wrapped into `RetriesExhausted`,
+/// with the number of attempts made, whether the attempts are over or the last one failed with
+/// the error which is not retryable. The error of the only attempt is returned as is.
+///
+/// ```
+/// use std::time::Duration;
+/// use std::default::Default;
+/// use cayley::Graph;
+/// use cayley::retry::RetryPolicy;
+///
+/// let graph = Graph::default().unwrap()
+///                   .with_retry(RetryPolicy { max_attempts: 5,
+///                                             max_backoff: Duration::seconds(10),
+///                                             ..Default::default() });
+/// ```
+#[deriving(Clone, PartialEq, Show)]
+pub struct RetryPolicy {
+    /// Number of attempts to make, including the first one, so `1` means no retries
+    pub max_attempts: uint,
+    /// Time to wait before the second attempt
+    pub initial_backoff: Duration,
+    /// Limit of the time to wait between the attempts
+    pub max_backoff: Duration,
+    /// Factor the backoff is multiplied by after every attempt
+    pub multiplier: f64,
+    /// Randomize the backoff
+    pub jitter: bool
+}
+
+impl RetryPolicy {
+
+    /// The policy which makes a single attempt and never repeats the requests
+    pub fn none() -> RetryPolicy {
+        RetryPolicy { max_attempts: 1, ..Default::default() }
+    }
+
+    /// Time to wait before the given attempt (counted from `1`, the first one is not delayed),
+    /// without the jitter
+    pub fn backoff(&self, attempt: uint) -> Duration {
+        if attempt <= 1 { return Duration::zero(); }
+        let initial = self.initial_backoff.num_milliseconds() as f64;
+        let backoff = initial * self.multiplier.powi((attempt - 2) as i32);
+        let max = self.max_backoff.num_milliseconds();
+        match backoff < max as f64 {
+            true => Duration::milliseconds(backoff as i64),
+            false => self.max_backoff
+        }
+    }
+
+    /// Call the operation until it succeeds, fails with the error which is not retryable,
+    /// or the attempts are over. The operation gets the number of the attempt.
+    pub fn run<T>(&self, operation: |uint| -> GraphResult<T>) -> GraphResult<T> {
+        let attempts = cmp::max(self.max_attempts, 1);
+        let mut attempt = 1u;
+        loop {
+            match operation(attempt) {
+                Ok(result) => return Ok(result),
+                Err(error) => {
+                    let last = !error.is_retryable() || attempt >= attempts;
+                    match (last, attempt) {
+                        (false, _) => (),
+                        (true, 1) => return Err(error),
+                        (true, _) => return Err(RetriesExhausted(box error, attempt))
+                    }
+                }
+            }
+            attempt += 1;
+            let delay = self.delay(attempt);
+            if delay > Duration::zero() { timer::sleep(delay); }
+        }
+    }
+
+    // backoff before the attempt with a random part of it cut
+    fn delay(&self, attempt: uint) -> Duration {
+        let backoff = self.backoff(attempt);
+        match self.jitter {
+            false => backoff,
+            true => {
+                let millis = backoff.num_milliseconds() as f64;
+                Duration::milliseconds((millis * (0.5 + rand::random::<f64>() / 2.0)) as i64)
+            }
+        }
+    }
+
+}
+
+impl Default for RetryPolicy {
+    fn default() -> RetryPolicy {
+        RetryPolicy { max_attempts: 3,
+                      initial_backoff: Duration::milliseconds(100),
+                      max_backoff: Duration::seconds(2),
+                      multiplier: 2.0,
+                      jitter: true }
+    }
+}
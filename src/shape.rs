@@ -0,0 +1,88 @@
+use serialize::{Decoder, Decodable};
+
+/// The tree of iterators Cayley builds to execute a query, as returned by the Shape API.
+///
+/// Use `graph.shape(<FinalizedQuery>)` to get it for any [finalized](../path/struct.FinalizedQuery.html) query,
+/// the query itself is not executed in this case.
+#[deriving(Clone, PartialEq, Show)]
+pub struct Shape {
+    pub nodes: Vec<ShapeNode>,
+    pub links: Vec<ShapeLink>
+}
+
+/// A node of the query [Shape](./struct.Shape.html): either a set of values, fixed or
+/// produced by iterators, or a link node which joins other nodes with a predicate
+#[deriving(Clone, PartialEq, Show)]
+pub struct ShapeNode {
+    pub id: int,
+    /// Tags assigned to the values of this node with `.As(...)`/`.Tag(...)`
+    pub tags: Vec<String>,
+    /// Values of this node, if they are fixed (like a start of a path)
+    pub values: Vec<String>,
+    /// `true` if this node joins other nodes with a predicate
+    pub is_link_node: bool,
+    /// `true` if values of this node are known before the query is executed
+    pub is_fixed: bool
+}
+
+/// A directed link between two nodes of the query [Shape](./struct.Shape.html)
+#[deriving(Clone, PartialEq, Show)]
+pub struct ShapeLink {
+    pub source: int,
+    pub target: int,
+    /// Id of the node holding the predicate of this link
+    pub predicate: int,
+    /// Id of the link node this link passes through
+    pub link_node: int
+}
+
+impl<S: Decoder<E>, E> Decodable<S, E> for Shape {
+    fn decode(decoder: &mut S) -> Result<Shape, E> {
+        decoder.read_struct("Shape", 2, |decoder| {
+            Ok(Shape {
+                nodes: try!(decoder.read_struct_field("nodes", 0, |decoder| read_list(decoder))),
+                links: try!(decoder.read_struct_field("links", 1, |decoder| read_list(decoder)))
+            })
+        })
+    }
+}
+
+impl<S: Decoder<E>, E> Decodable<S, E> for ShapeNode {
+    fn decode(decoder: &mut S) -> Result<ShapeNode, E> {
+        decoder.read_struct("ShapeNode", 5, |decoder| {
+            Ok(ShapeNode {
+                id: try!(decoder.read_struct_field("id", 0, |decoder| decoder.read_int())),
+                tags: try!(decoder.read_struct_field("tags", 1, |decoder| read_list(decoder))),
+                values: try!(decoder.read_struct_field("values", 2, |decoder| read_list(decoder))),
+                is_link_node: try!(decoder.read_struct_field("is_link_node", 3,
+                                                             |decoder| decoder.read_bool())),
+                is_fixed: try!(decoder.read_struct_field("is_fixed", 4,
+                                                         |decoder| decoder.read_bool()))
+            })
+        })
+    }
+}
+
+impl<S: Decoder<E>, E> Decodable<S, E> for ShapeLink {
+    fn decode(decoder: &mut S) -> Result<ShapeLink, E> {
+        decoder.read_struct("ShapeLink", 4, |decoder| {
+            Ok(ShapeLink {
+                source: try!(decoder.read_struct_field("source", 0, |decoder| decoder.read_int())),
+                target: try!(decoder.read_struct_field("target", 1, |decoder| decoder.read_int())),
+                predicate: try!(decoder.read_struct_field("type", 2, |decoder| decoder.read_int())),
+                link_node: try!(decoder.read_struct_field("link_node", 3,
+                                                          |decoder| decoder.read_int()))
+            })
+        })
+    }
+}
+
+// Cayley omits empty lists from the shape or sends them as `null`
+fn read_list<S: Decoder<E>, E, T: Decodable<S, E>>(decoder: &mut S) -> Result<Vec<T>, E> {
+    decoder.read_option(|decoder, has_value| {
+        match has_value {
+            false => Ok(Vec::new()),
+            true => Decodable::decode(decoder)
+        }
+    })
+}
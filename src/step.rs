@@ -0,0 +1,466 @@
+use selector::{NodeSelector, TagSelector, PredicateSelector};
+
+use selector::{AnyNode, Node, Nodes};
+use selector::{AnyTag, Tag, Tags};
+use selector::{AnyPredicate, Predicate, Predicates};
+use selector::Query as FromQuery;
+
+use path::Compile;
+
+use errors::{GraphResult, QueryBuildFailed};
+use errors::{BuildError, EmptyPath, NotStarted, StartedTwice, FinalizedTwice, StepAfterFinalizer,
+             SubQueryFailed, InvalidName};
+
+/// A query as a tree of typed steps, the way [Vertex](../path/struct.Vertex.html) and
+/// [Morphism](../path/struct.Morphism.html) store it before it is compiled to Gremlin.
+///
+/// Use `.traversal()` of any [Compile](../path/trait.Compile.html) instance to inspect a query
+/// and `.traversal_mut()` to rewrite it:
+///
+/// ```
+/// use cayley::path::{Vertex, Path, Compile};
+/// use cayley::selector::{Node, Predicate};
+/// use cayley::step::{Out, Single};
+///
+/// let mut v = Vertex::start(Node("C"));
+/// v.OutP(Predicate("follows"));
+/// for step in v.traversal_mut().steps.iter_mut() {
+///     match *step {
+///         Out(Single(ref mut predicate), _) => predicate.push_str("_twice"),
+///         _ => ()
+///     }
+/// }
+/// assert_eq!(v.compile().ok(), Some("g.V(\"C\").Out(\"follows_twice\")".to_string()));
+/// ```
+#[deriving(Clone, PartialEq, Show)]
+pub struct Traversal {
+    /// Morphisms this traversal follows, by their names, declared before the traversal itself
+    pub includes: Vec<(String, Traversal)>,
+    pub steps: Vec<Step>,
+    errors: Vec<BuildError>
+}
+
+/// A single step of a [Traversal](./struct.Traversal.html), one per every method called on a path
+#[deriving(Clone, PartialEq, Show)]
+pub enum Step {
+    /// `g.V(<nodes>)`
+    StartVertex(Arg),
+    /// `g.M()`
+    StartMorphism,
+    /// `.Out(<predicates>, <tags>)`
+    Out(Arg, Arg),
+    /// `.In(<predicates>, <tags>)`
+    In(Arg, Arg),
+    /// `.Both(<predicates>, <tags>)`
+    Both(Arg, Arg),
+    /// `.Is(<nodes>)`
+    Is(Arg),
+    /// `.Has(<predicates>, <nodes>)`
+    Has(Arg, Arg),
+    /// `.As(<tags>)`
+    As(Arg),
+    /// `.Back(<tags>)`
+    Back(Arg),
+    /// `.Save(<predicates>, <tags>)`
+    Save(Arg, Arg),
+    /// `.And(<query>)`
+    And(Box<Traversal>),
+    /// `.Or(<query>)`
+    Or(Box<Traversal>),
+    /// `.Follow(<morphism name>)`
+    Follow(String),
+    /// `.FollowR(<morphism name>)`
+    FollowR(String),
+    /// Any of the [Query](../path/trait.Query.html) finalizers
+    Final(Finalizer)
+}
+
+/// An argument of a [Step](./enum.Step.html), converted from any of the selectors
+#[deriving(Clone, PartialEq, Show)]
+pub enum Arg {
+    /// `AnyNode`, `AnyPredicate` or `AnyTag`
+    Unset,
+    /// `Node(...)`, `Predicate(...)` or `Tag(...)`
+    Single(String),
+    /// `Nodes(...)`, `Predicates(...)` or `Tags(...)`
+    Multiple(Vec<String>),
+    /// A predicate taken from another query
+    SubQuery(Box<Traversal>)
+}
+
+/// The method a [Query](../path/trait.Query.html) was finalized with, it defines the
+/// shape of the [QueryResult](../graph/enum.QueryResult.html) returned for this query.
+#[deriving(Clone, PartialEq, Show)]
+pub enum Finalizer {
+    All,
+    GetLimit(int),
+    ToArray,
+    ToValue,
+    TagArray,
+    TagValue,
+    ForEach(Option<int>, String)
+}
+
+// ================================ Traversal =============================== //
+
+impl Traversal {
+
+    pub fn new() -> Traversal {
+        Traversal { includes: Vec::new(), steps: Vec::with_capacity(10), errors: Vec::new() }
+    }
+
+    /// Add the step to the end of this traversal, or record an error instead if the step
+    /// is not allowed there, see [BuildError](../errors/enum.BuildError.html)
+    pub fn push(&mut self, step: Step) {
+        let mut failed = Vec::new();
+        for query in step.sub_queries().iter() {
+            failed.push_all(query.errors().as_slice());
+        }
+        match step {
+            Follow(ref name) | FollowR(ref name) =>
+                match self.includes.iter().rev().find(|&&(ref included, _)| included == name) {
+                    Some(&(_, ref morphism)) => failed.push_all(morphism.errors().as_slice()),
+                    None => ()
+                },
+            _ => ()
+        }
+        let invalid_name = match step {
+            Follow(ref name) | FollowR(ref name) if !is_identifier(name.as_slice()) => Some(name.clone()),
+            _ => None
+        };
+        let error = if !failed.is_empty() {
+            Some(SubQueryFailed(step.clone(), failed))
+        } else if invalid_name.is_some() {
+            invalid_name.map(|name| InvalidName(name))
+        } else if step.is_start() && !self.steps.is_empty() {
+            Some(StartedTwice(step.clone()))
+        } else if !step.is_start() && self.steps.is_empty() {
+            Some(NotStarted(step.clone()))
+        } else if self.finalizer().is_some() {
+            match step {
+                Final(_) => Some(FinalizedTwice(step.clone())),
+                _ => Some(StepAfterFinalizer(step.clone()))
+            }
+        } else { None };
+        match error {
+            Some(error) => self.errors.push(error),
+            None => self.steps.push(step)
+        }
+    }
+
+    /// Record `InvalidName` error, if the name this traversal is to be declared with, as a
+    /// JavaScript variable, is not a valid identifier
+    pub fn check_name(&mut self, name: &str) {
+        if !is_identifier(name) { self.errors.push(InvalidName(name.to_string())); }
+    }
+
+    /// The errors made while building this traversal, the steps which caused them are not added
+    pub fn errors(&self) -> Vec<BuildError> {
+        match self.steps.is_empty() && self.errors.is_empty() {
+            true => vec!(EmptyPath),
+            false => self.errors.clone()
+        }
+    }
+
+    /// Render this traversal as a Gremlin-compatible string, fails with `QueryBuildFailed`
+    /// if there were errors made while building it
+    pub fn compile(&self) -> GraphResult<String> {
+        let errors = self.errors();
+        match errors.is_empty() {
+            true => Ok(self.render()),
+            false => Err(QueryBuildFailed(errors))
+        }
+    }
+
+    /// Render this traversal as a declaration of the JavaScript variable with the given name,
+    /// `var <name> = <path>`, fails with `QueryBuildFailed` the way `compile` does
+    pub fn compile_as(&self, name: &str) -> GraphResult<String> {
+        let errors = self.errors();
+        match errors.is_empty() {
+            true => Ok(self.with_declarations(format!("var {:s} = {:s}", name, self.render_path()))),
+            false => Err(QueryBuildFailed(errors))
+        }
+    }
+
+    /// The finalizer this traversal ends with, if any
+    pub fn finalizer(&self) -> Option<&Finalizer> {
+        self.steps.iter().rev().filter_map(|step| match *step {
+            Final(ref finalizer) => Some(finalizer),
+            _ => None
+        }).next()
+    }
+
+    /// Declare the morphism to be followed by this traversal
+    pub fn include(&mut self, name: &str, morphism: &Traversal) {
+        self.includes.push((name.to_string(), morphism.clone()));
+    }
+
+    /// Render this traversal as a Gremlin-compatible string, the morphisms followed by it or by
+    /// its sub-queries are declared at the start of the string, before the path
+    pub fn render(&self) -> String {
+        self.with_declarations(self.render_path())
+    }
+
+    // puts the declarations of the followed morphisms before the rendered path
+    fn with_declarations(&self, path: String) -> String {
+        let mut declarations = Vec::new();
+        self.declare(&mut declarations);
+        match declarations.is_empty() {
+            true => path,
+            false => declarations.connect(";") + ";".to_string() + path
+        }
+    }
+
+    // renders the steps only, the followed morphisms are expected to be declared already
+    fn render_path(&self) -> String {
+        let steps: Vec<String> = self.steps.iter().map(|step| step.render()).collect();
+        match self.finalizer() {
+            Some(finalizer) if finalizer.needs_emit() => format!("g.Emit({:s})", steps.connect(".")),
+            _ => steps.connect(".")
+        }
+    }
+
+    // collects `var <name> = <morphism>` declarations of the morphisms followed by this traversal
+    // and by its sub-queries, a morphism is declared after the ones it follows itself, and
+    // the one followed several times is declared once
+    fn declare(&self, declarations: &mut Vec<String>) {
+        for &(ref name, ref morphism) in self.includes.iter() {
+            morphism.declare(declarations);
+            let declaration = format!("var {:s} = {:s}", name, morphism.render_path());
+            if !declarations.contains(&declaration) { declarations.push(declaration); }
+        }
+        for step in self.steps.iter() {
+            for query in step.sub_queries().iter() {
+                query.declare(declarations);
+            }
+        }
+    }
+
+}
+
+// ================================ Step ==================================== //
+
+impl Step {
+
+    /// The name of the method this step is added with
+    pub fn name(&self) -> &'static str {
+        match *self {
+            StartVertex(_) => "From",
+            StartMorphism => "M",
+            Out(..) => "Out",
+            In(..) => "In",
+            Both(..) => "Both",
+            Is(_) => "Is",
+            Has(..) => "Has",
+            As(_) => "As",
+            Back(_) => "Back",
+            Save(..) => "Save",
+            And(_) => "And",
+            Or(_) => "Or",
+            Follow(_) => "Follow",
+            FollowR(_) => "FollowR",
+            Final(ref finalizer) => match *finalizer {
+                All => "All",
+                GetLimit(_) => "GetLimit",
+                ToArray => "ToArray",
+                ToValue => "ToValue",
+                TagArray => "TagArray",
+                TagValue => "TagValue",
+                ForEach(..) => "ForEach"
+            }
+        }
+    }
+
+    fn is_start(&self) -> bool {
+        match *self {
+            StartVertex(_) | StartMorphism => true,
+            _ => false
+        }
+    }
+
+    // the queries passed to this step as arguments
+    fn sub_queries(&self) -> Vec<&Traversal> {
+        match *self {
+            And(ref query) | Or(ref query) => vec!(&**query),
+            Out(SubQuery(ref query), _) | In(SubQuery(ref query), _) | Both(SubQuery(ref query), _) |
+            Has(SubQuery(ref query), _) | Save(SubQuery(ref query), _) => vec!(&**query),
+            _ => Vec::new()
+        }
+    }
+
+    /// Render this step as a Gremlin-compatible string, the morphisms followed by the sub-queries
+    /// are not declared here, but by the traversal which holds the step
+    pub fn render(&self) -> String {
+        match *self {
+            StartVertex(ref nodes) => format!("g.V({:s})", list(nodes)),
+            StartMorphism => "g.M()".to_string(),
+            Out(ref predicates, ref tags) => format!("Out({:s})", pair(predicates, tags, ", ")),
+            In(ref predicates, ref tags) => format!("In({:s})", pair(predicates, tags, ", ")),
+            Both(ref predicates, ref tags) => format!("Both({:s})", pair(predicates, tags, ", ")),
+            Is(ref nodes) => format!("Is({:s})", list(nodes)),
+            Has(ref predicates, ref nodes) => format!("Has({:s})", pair(predicates, nodes, ",")),
+            As(ref tags) => format!("As({:s})", list(tags)),
+            Back(ref tags) => format!("Back({:s})", list(tags)),
+            Save(ref predicates, ref tags) => format!("Save({:s})", pair(predicates, tags, ", ")),
+            And(ref query) => format!("And({:s})", query.render_path()),
+            Or(ref query) => format!("Or({:s})", query.render_path()),
+            Follow(ref name) => format!("Follow({:s})", name),
+            FollowR(ref name) => format!("FollowR({:s})", name),
+            Final(ref finalizer) => finalizer.render()
+        }
+    }
+
+}
+
+// ================================ Arg ===================================== //
+
+impl Arg {
+
+    pub fn from_nodes(nodes: NodeSelector) -> Arg {
+        match nodes {
+            AnyNode => Unset,
+            Node(name) => Single(name.to_string()),
+            Nodes(names) => Multiple(names.iter().map(|name| name.to_string()).collect())
+        }
+    }
+
+    pub fn from_predicates(predicates: PredicateSelector) -> Arg {
+        match predicates {
+            AnyPredicate => Unset,
+            Predicate(name) => Single(name.to_string()),
+            Predicates(names) => Multiple(names.iter().map(|name| name.to_string()).collect()),
+            FromQuery(query) => SubQuery(box query.traversal().clone())
+        }
+    }
+
+    pub fn from_tags(tags: TagSelector) -> Arg {
+        match tags {
+            AnyTag => Unset,
+            Tag(name) => Single(name.to_string()),
+            Tags(names) => Multiple(names.iter().map(|name| name.to_string()).collect())
+        }
+    }
+
+}
+
+// ================================ Finalizer =============================== //
+
+impl Finalizer {
+
+    // finalizers which return a value rather than pass the results to the server themselves,
+    // so the value has to be passed with `g.Emit(...)`
+    fn needs_emit(&self) -> bool {
+        match *self {
+            All | GetLimit(_) | ForEach(..) => false,
+            ToArray | ToValue | TagArray | TagValue => true
+        }
+    }
+
+    /// Render this finalizer as a Gremlin-compatible string
+    pub fn render(&self) -> String {
+        match *self {
+            All => "All()".to_string(),
+            GetLimit(limit) => format!("GetLimit({:i})", limit),
+            ToArray => "ToArray()".to_string(),
+            ToValue => "ToValue()".to_string(),
+            TagArray => "TagArray()".to_string(),
+            TagValue => "TagValue()".to_string(),
+            ForEach(None, ref callback) => format!("ForEach(function(d) {{ {:s} }})", callback),
+            ForEach(Some(limit), ref callback) =>
+                format!("ForEach({:i}, function(d) {{ {:s} }})", limit, callback)
+        }
+    }
+
+}
+
+// ================================ utils =================================== //
+
+// arguments passed one after another: `"a","b"`
+fn list(arg: &Arg) -> String {
+    match *arg {
+        Unset => "".to_string(),
+        Single(ref value) => literal(value.as_slice()),
+        Multiple(ref values) => literals(values.as_slice()),
+        SubQuery(ref query) => query.render_path()
+    }
+}
+
+// a pair of arguments where the second one is optional: `"a",["b","c"]` or `null,"b"`,
+// (NB: a sub-query is separated from the second argument with `separator`)
+fn pair(first: &Arg, second: &Arg, separator: &str) -> String {
+    let rest = match *second {
+        Unset => return match *first {
+            Unset => "".to_string(),
+            _ => item(first)
+        },
+        _ => item(second)
+    };
+    match *first {
+        Unset => format!("null,{:s}", rest),
+        SubQuery(_) => format!("{:s}{:s}{:s}", item(first), separator, rest),
+        _ => format!("{:s},{:s}", item(first), rest)
+    }
+}
+
+// an argument passed as a single value: `"a"` or `["a","b"]`
+fn item(arg: &Arg) -> String {
+    match *arg {
+        Unset => "null".to_string(),
+        Single(ref value) => literal(value.as_slice()),
+        Multiple(ref values) => format!("[{:s}]", literals(values.as_slice())),
+        SubQuery(ref query) => query.render_path()
+    }
+}
+
+/// Quote the value as a JavaScript string literal, so any node, predicate or tag name
+/// is passed to the query as is and never breaks or alters the query itself.
+///
+/// ```
+/// use cayley::step::literal;
+///
+/// assert_eq!(literal("He said \"hi\""), "\"He said \\\"hi\\\"\"".to_string());
+/// ```
+pub fn literal(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            // these are line terminators in JavaScript, but not in JSON
+            '\u2028' | '\u2029' => quoted.push_str(format!("\\u{:04x}", c as u32).as_slice()),
+            c if c < ' ' || c == '\x7f' => quoted.push_str(format!("\\u{:04x}", c as u32).as_slice()),
+            c => quoted.push(c)
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+// quotes every value and joins them with commas
+fn literals(values: &[String]) -> String {
+    values.iter().map(|value| literal(value.as_slice())).collect::<Vec<String>>().connect(",")
+}
+
+// the words which can't be used as JavaScript variable names, and `g`, which is the graph itself
+static RESERVED_WORDS: &'static [&'static str] = &[
+    "break", "case", "catch", "class", "const", "continue", "debugger", "default", "delete", "do",
+    "else", "enum", "export", "extends", "false", "finally", "for", "function", "if", "import",
+    "in", "instanceof", "new", "null", "return", "super", "switch", "this", "throw", "true", "try",
+    "typeof", "var", "void", "while", "with", "let", "static", "yield", "implements", "interface",
+    "package", "private", "protected", "public", "g"
+];
+
+// `true` if the name may be declared as a JavaScript variable, morphism names are put
+// into the query as they are, so anything else would break it
+fn is_identifier(name: &str) -> bool {
+    let identifier_char = |c: char| c.is_alphanumeric() || c == '_' || c == '$';
+    match name.chars().next() {
+        None => false,
+        Some(first) if first.is_digit() => false,
+        Some(_) => name.chars().all(identifier_char) && !RESERVED_WORDS.iter().any(|word| *word == name)
+    }
+}
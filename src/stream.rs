@@ -0,0 +1,158 @@
+use std::cmp;
+use std::io::{IoError, EndOfFile};
+use std::rc::Rc;
+use std::cell::RefCell;
+
+use std::collections::HashMap;
+
+use serialize::json;
+
+use graph::GraphNode;
+use events::{ResultEvents, ResultError,
+             NodeStart, NodeTag, NodeEnd, NodeId, NullResult, SyntaxError, NestedValue};
+
+use errors::{GraphResult, DecodingFailed, ResponseParseFailed, request_failed};
+
+/// Iterator over the nodes of a response, returned from `graph.find_iter(<FinalizedQuery>)`
+///
+/// The response is parsed while it is read from the connection, and every node is
+/// given out as soon as it is read completely, so just one node is stored at a time,
+/// no matter how many nodes the query returns.
+///
+/// Nodes are found the same way [CompactNodes](../compact/struct.CompactNodes.html) do: every
+/// object in the `result` of the response is a node, and a bare id is a node with the only
+/// `id` tag. The iterator stops after the first error. The response is not kept, so when it can
+/// not be parsed, `DecodingFailed` error holds just the part of it read before the error, up to
+/// the last 256 characters.
+///
+/// When Cayley replied with `null` instead of the result, there are no nodes, and `is_null()`
+/// tells it apart from an empty result once the iteration is over, so iterate `by_ref()` to
+/// check it afterwards.
+///
+/// ```
+/// use cayley::graph::{Graph, GraphNode};
+/// use cayley::path::{Vertex, Query};
+/// use cayley::selector::AnyNode;
+///
+/// let graph = Graph::default().unwrap();
+/// for node in graph.find_iter(Vertex::start(AnyNode).All()).unwrap() {
+///     let GraphNode(tags) = node.unwrap();
+///     println!("{}", tags.find(&"id".to_string()));
+/// }
+/// ```
+pub struct GraphNodesIter<R> {
+    events: ResultEvents<ReaderChars<R>>,
+    // the first I/O error met while reading, shared with the reader
+    io_error: Rc<RefCell<Option<IoError>>>,
+    // the end of the response read so far, shared with the reader
+    recent: Rc<RefCell<String>>,
+    // the query which is executed, to be reported on I/O errors
+    query: String,
+    // `true` if the result was `null`
+    null: bool,
+    done: bool
+}
+
+// passes the characters of the reader to the JSON parser, the parser has no way
+// to tell about the I/O errors itself, so the first one is kept here, and no way
+// to tell where the syntax error is, so the last characters read are kept too
+struct ReaderChars<R> {
+    reader: R,
+    error: Rc<RefCell<Option<IoError>>>,
+    recent: Rc<RefCell<String>>
+}
+
+// number of the last characters of the response reported with `DecodingFailed` error
+static RECENT_CHARS: uint = 256;
+
+impl<R: Buffer> GraphNodesIter<R> {
+
+    /// Read the nodes from a response which is not read yet, `query` is the query
+    /// which was sent to get this response, it is reported when the response fails to be read
+    pub fn new(reader: R, query: String) -> GraphNodesIter<R> {
+        let io_error = Rc::new(RefCell::new(None));
+        let recent = Rc::new(RefCell::new(String::new()));
+        GraphNodesIter { events: ResultEvents::new(ReaderChars { reader: reader,
+                                                                 error: io_error.clone(),
+                                                                 recent: recent.clone() }),
+                         io_error: io_error,
+                         recent: recent,
+                         query: query,
+                         null: false,
+                         done: false }
+    }
+
+    /// `true` if Cayley replied with `null` instead of the result, i.e. the start node is not
+    /// in the database at all, while an empty result means that nothing was found on the path;
+    /// known only when the nodes are read
+    pub fn is_null(&self) -> bool { self.null }
+
+    // the I/O error, if it happened, is the real cause of any parsing error
+    fn fail(&mut self, error: ResultError) -> Option<GraphResult<GraphNode>> {
+        self.done = true;
+        let io_error = self.io_error.borrow_mut().take();
+        match (io_error, error) {
+            (Some(io_error), _) => Some(Err(request_failed(io_error, self.query.clone()))),
+            (None, SyntaxError(error)) =>
+                Some(Err(DecodingFailed(json::ParseError(error), self.recent_response()))),
+            (None, NestedValue) => Some(Err(ResponseParseFailed))
+        }
+    }
+
+    // the last characters of the response read so far
+    fn recent_response(&self) -> String {
+        let recent = self.recent.borrow();
+        let skip = cmp::max(recent.as_slice().char_len(), RECENT_CHARS) - RECENT_CHARS;
+        recent.as_slice().chars().skip(skip).collect()
+    }
+
+}
+
+impl<R: Buffer> Iterator<GraphResult<GraphNode>> for GraphNodesIter<R> {
+    fn next(&mut self) -> Option<GraphResult<GraphNode>> {
+        if self.done { return None; }
+        let mut tags = HashMap::new();
+        loop {
+            match self.events.next() {
+                None => {
+                    self.done = true;
+                    let io_error = self.io_error.borrow_mut().take();
+                    return match io_error {
+                        Some(io_error) => Some(Err(request_failed(io_error, self.query.clone()))),
+                        None => None
+                    };
+                },
+                Some(Ok(NodeStart)) => tags = HashMap::new(),
+                Some(Ok(NodeTag(key, value))) => { tags.insert(key, value); },
+                Some(Ok(NodeEnd)) => return Some(Ok(GraphNode(tags))),
+                Some(Ok(NodeId(id))) => {
+                    let mut tags = HashMap::new();
+                    tags.insert("id".to_string(), id);
+                    return Some(Ok(GraphNode(tags)));
+                },
+                Some(Ok(NullResult)) => self.null = true,
+                Some(Err(error)) => return self.fail(error)
+            }
+        }
+    }
+}
+
+impl<R: Buffer> Iterator<char> for ReaderChars<R> {
+    fn next(&mut self) -> Option<char> {
+        match self.reader.read_char() {
+            Ok(c) => {
+                let mut recent = self.recent.borrow_mut();
+                // the older characters are cut from time to time, not after every one read
+                if recent.len() >= RECENT_CHARS * 8 {
+                    let skip = recent.as_slice().char_len() - RECENT_CHARS;
+                    let kept = recent.as_slice().chars().skip(skip).collect();
+                    *recent = kept;
+                }
+                recent.push(c);
+                Some(c)
+            },
+            Err(ref error) if error.kind == EndOfFile => None,
+            Err(error) => { *self.error.borrow_mut() = Some(error); None }
+        }
+    }
+}
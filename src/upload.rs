@@ -0,0 +1,140 @@
+use std::io::{IoResult, IoError, EndOfFile};
+use std::rand;
+
+// size of a single chunk read from the source and sent to the server
+static CHUNK_SIZE: uint = 64 * 1024;
+
+/// Progress of an N-Quads upload, reported every time a chunk of the document is sent
+#[deriving(Clone, PartialEq, Show)]
+pub struct UploadProgress {
+    /// Bytes of the document sent so far
+    pub bytes: u64,
+    /// Statements of the document sent so far (empty lines and comments are not counted)
+    pub quads: u64
+}
+
+/// Tells which side of the upload failed
+pub enum StreamError {
+    SourceFailed(IoError),
+    TargetFailed(IoError)
+}
+
+// tracks the state of the current line to count the statements passed through
+enum LineState { LineStart, InStatement, InComment }
+
+struct QuadCounter {
+    quads: u64,
+    state: LineState
+}
+
+impl QuadCounter {
+
+    fn new() -> QuadCounter { QuadCounter { quads: 0, state: LineStart } }
+
+    fn feed(&mut self, chunk: &[u8]) {
+        for byte in chunk.iter() {
+            self.state = match (self.state, *byte) {
+                (InStatement, b'\n') => { self.quads += 1; LineStart },
+                (_, b'\n') => LineStart,
+                (LineStart, b' ') | (LineStart, b'\t') | (LineStart, b'\r') => LineStart,
+                (LineStart, b'#') => InComment,
+                (LineStart, _) => InStatement,
+                (state, _) => state
+            }
+        }
+    }
+
+    fn finish(&mut self) {
+        match self.state {
+            InStatement => { self.quads += 1; self.state = LineStart },
+            _ => ()
+        }
+    }
+
+}
+
+/// Generate a multipart boundary which is unlikely to appear inside a document
+pub fn boundary() -> String {
+    format!("cayley-rust-{:016x}", rand::random::<u64>())
+}
+
+/// Send the contents of `source` to `target` as a single `NQuadFile` field of a
+/// multipart form, using chunked transfer encoding, since the size of the source
+/// is not known in advance. Calls `progress` after every chunk sent.
+pub fn stream_multipart<R: Reader, W: Writer>(source: &mut R, target: &mut W,
+                                              boundary: &str, filename: &str,
+                                              progress: |UploadProgress|)
+                                              -> Result<UploadProgress, StreamError> {
+    let head = format!("--{:s}\r\n\
+                        Content-Disposition: form-data; name=\"NQuadFile\"; filename=\"{:s}\"\r\n\
+                        Content-Type: application/octet-stream\r\n\r\n",
+                       boundary, filename.replace("\"", "'"));
+    match write_chunk(target, head.as_bytes()) {
+        Err(error) => return Err(TargetFailed(error)), Ok(_) => ()
+    }
+    let sent = match stream_chunks(source, target, progress) {
+        Err(error) => return Err(error), Ok(sent) => sent
+    };
+    let tail = format!("\r\n--{:s}--\r\n", boundary);
+    match write_chunk(target, tail.as_bytes()).and_then(|_| write_last_chunk(target)) {
+        Err(error) => Err(TargetFailed(error)),
+        Ok(_) => Ok(sent)
+    }
+}
+
+/// Send the contents of `source` to `target` as is, using chunked transfer encoding.
+/// Calls `progress` after every chunk sent.
+pub fn stream_raw<R: Reader, W: Writer>(source: &mut R, target: &mut W,
+                                        progress: |UploadProgress|)
+                                        -> Result<UploadProgress, StreamError> {
+    let sent = match stream_chunks(source, target, progress) {
+        Err(error) => return Err(error), Ok(sent) => sent
+    };
+    match write_last_chunk(target) {
+        Err(error) => Err(TargetFailed(error)),
+        Ok(_) => Ok(sent)
+    }
+}
+
+// passes the source to the target chunk by chunk, counting the statements sent
+fn stream_chunks<R: Reader, W: Writer>(source: &mut R, target: &mut W,
+                                       progress: |UploadProgress|)
+                                       -> Result<UploadProgress, StreamError> {
+    let mut counter = QuadCounter::new();
+    let mut sent = UploadProgress { bytes: 0, quads: 0 };
+    let mut buffer = Vec::from_elem(CHUNK_SIZE, 0u8);
+    loop {
+        match source.read(buffer.as_mut_slice()) {
+            Err(ref error) if error.kind == EndOfFile => break,
+            Err(error) => return Err(SourceFailed(error)),
+            Ok(len) => {
+                let chunk = buffer.slice_to(len);
+                match write_chunk(target, chunk) {
+                    Err(error) => return Err(TargetFailed(error)), Ok(_) => ()
+                }
+                counter.feed(chunk);
+                sent = UploadProgress { bytes: sent.bytes + len as u64, quads: counter.quads };
+                progress(sent);
+            }
+        }
+    }
+    counter.finish();
+    if counter.quads != sent.quads {
+        sent.quads = counter.quads;
+        progress(sent);
+    }
+    Ok(sent)
+}
+
+// writes data framed as a single HTTP chunk
+fn write_chunk<W: Writer>(target: &mut W, data: &[u8]) -> IoResult<()> {
+    if data.is_empty() { return Ok(()) }
+    try!(target.write_str(format!("{:x}\r\n", data.len()).as_slice()));
+    try!(target.write(data));
+    target.write_str("\r\n")
+}
+
+// writes the zero-length chunk which ends the body
+fn write_last_chunk<W: Writer>(target: &mut W) -> IoResult<()> {
+    target.write_str("0\r\n\r\n")
+}
@@ -1,11 +1,25 @@
 extern crate cayley;
+extern crate serialize;
+
+use serialize::json;
 
 use cayley::graph::{Graph, V1};
 use cayley::graph::{GraphNodes, GraphNode};
+use cayley::graph::{NodeMaps, NodeArray, NodeValue, NodeMap, Emitted, NullResult};
+use cayley::graph::{Quad, Written, Refused};
 use cayley::path::{Morphism, Vertex, Path, Query};
 use cayley::selector::{AnyNode, Node};
-use cayley::selector::AnyTag;
+use cayley::selector::{AnyTag, Tag};
 use cayley::selector::Predicate;
+use cayley::mql;
+use cayley::mql::Mql;
+use cayley::errors::ServerError;
+
+#[deriving(Decodable)]
+struct Movie {
+    id: String,
+    name: String
+}
 
 #[test]
 fn main() {
@@ -24,25 +38,27 @@ fn main() {
             match graph.find(Vertex::start(AnyNode).All()) {
 
                 Err(error) => panic!(error.to_string()),
-                Ok(GraphNodes(nodes)) => {
+                Ok(NodeMaps(GraphNodes(nodes))) => {
                     assert!(nodes.len() > 0);
-                }
+                },
+                Ok(_) => panic!("tag maps were expected")
 
             };
 
             match graph.find(Vertex::start(AnyNode).GetLimit(5)) {
 
                 Err(error) => panic!(error.to_string()),
-                Ok(GraphNodes(nodes)) => {
+                Ok(NodeMaps(GraphNodes(nodes))) => {
                     assert_eq!(nodes.len(), 5);
-                }
+                },
+                Ok(_) => panic!("tag maps were expected")
 
             };
 
             match graph.find(Vertex::start(Node("Humphrey Bogart")).All()) {
 
                 Err(error) => panic!(error.to_string()),
-                Ok(GraphNodes(nodes)) => {
+                Ok(NodeMaps(GraphNodes(nodes))) => {
                     assert_eq!(nodes.len(), 1);
                     match nodes.iter().next() {
                         Some(&GraphNode(ref humphrey)) => {
@@ -50,7 +66,8 @@ fn main() {
                         },
                         None => panic!("first node was not found")
                     }
-                }
+                },
+                Ok(_) => panic!("tag maps were expected")
 
             }
 
@@ -59,7 +76,7 @@ fn main() {
                                     .All()) {
 
                 Err(error) => panic!(error.to_string()),
-                Ok(GraphNodes(nodes)) => {
+                Ok(NodeMaps(GraphNodes(nodes))) => {
                     assert_eq!(nodes.len(), 1);
                     match nodes.iter().next() {
                         Some(&GraphNode(ref humphrey)) => {
@@ -68,7 +85,8 @@ fn main() {
                         },
                         None => panic!("first node was not found")
                     }
-                }
+                },
+                Ok(_) => panic!("tag maps were expected")
 
             }
 
@@ -77,7 +95,7 @@ fn main() {
                                     .All()) {
 
                 Err(error) => panic!(error.to_string()),
-                Ok(GraphNodes(nodes)) => {
+                Ok(NodeMaps(GraphNodes(nodes))) => {
                     assert_eq!(nodes.len(), 1);
                     match nodes.iter().next() {
                         Some(&GraphNode(ref casablanca)) => {
@@ -86,7 +104,8 @@ fn main() {
                         },
                         None => panic!("first node was not found")
                     }
-                }
+                },
+                Ok(_) => panic!("tag maps were expected")
 
             }
 
@@ -98,9 +117,10 @@ fn main() {
                                     .All()) {
 
                 Err(error) => panic!(error.to_string()),
-                Ok(GraphNodes(nodes)) => {
+                Ok(NodeMaps(GraphNodes(nodes))) => {
                     assert!(nodes.len() > 0);
-                }
+                },
+                Ok(_) => panic!("tag maps were expected")
             }
 
             let mut film_to_actor = Morphism::start("fta");
@@ -113,58 +133,163 @@ fn main() {
                                     .All()) {
 
                 Err(error) => panic!(error.to_string()),
-                Ok(GraphNodes(nodes)) => {
+                Ok(NodeMaps(GraphNodes(nodes))) => {
                     println!("{}",nodes.len());
                     assert!(nodes.len() > 0);
+                },
+                Ok(_) => panic!("tag maps were expected")
+            }
+
+            match graph.find(Vertex::start(Node("Casablanca"))
+                                    .InP(Predicate("name"))
+                                    .ToArray()) {
+
+                Err(error) => panic!(error.to_string()),
+                Ok(NodeArray(ids)) => {
+                    assert_eq!(ids, vec!("/en/casablanca_1942".to_string()));
+                },
+                Ok(_) => panic!("an array of ids was expected")
+
+            }
+
+            match graph.find(Vertex::start(Node("Unknown Movie"))
+                                    .InP(Predicate("name"))
+                                    .ToArray()) {
+
+                Err(error) => panic!(error.to_string()),
+                Ok(NodeArray(ids)) => assert!(ids.is_empty()),
+                Ok(NullResult) => panic!("an empty array is emitted even when nothing is found"),
+                Ok(_) => panic!("an array of ids was expected")
+
+            }
+
+            match graph.find(Vertex::start(Node("Casablanca"))
+                                    .InP(Predicate("name"))
+                                    .ToValue()) {
+
+                Err(error) => panic!(error.to_string()),
+                Ok(NodeValue(id)) => {
+                    assert_eq!(id, Some("/en/casablanca_1942".to_string()));
+                },
+                Ok(_) => panic!("a single id was expected")
+
+            }
+
+            match graph.find(Vertex::start(Node("Casablanca"))
+                                    .InP(Predicate("name"))
+                                    .TagValue()) {
+
+                Err(error) => panic!(error.to_string()),
+                Ok(NodeMap(Some(GraphNode(ref casablanca)))) => {
+                    assert_eq!(casablanca["id".to_string()].as_slice(), "/en/casablanca_1942");
+                },
+                Ok(_) => panic!("a single tag map was expected")
+
+            }
+
+            match graph.find_as::<Movie>(Vertex::start(Node("Casablanca"))
+                                                 .As(Tag("name"))
+                                                 .InP(Predicate("name"))
+                                                 .All()) {
+
+                Err(error) => panic!(error.to_string()),
+                Ok(movies) => {
+                    assert_eq!(movies.len(), 1);
+                    assert_eq!(movies[0].id.as_slice(), "/en/casablanca_1942");
+                    assert_eq!(movies[0].name.as_slice(), "Casablanca");
                 }
+
+            }
+
+            match graph.find(Vertex::start(Node("Casablanca")).ForEach("g.Emit(d)")) {
+
+                Err(error) => panic!(error.to_string()),
+                Ok(Emitted(values)) => {
+                    assert!(values.len() > 0);
+                },
+                Ok(_) => panic!("emitted values were expected")
+
+            }
+
+            match graph.exec_json("g.Emit({ count: g.V(\"Casablanca\").ToArray().length })".to_string()) {
+
+                Err(error) => panic!(error.to_string()),
+                Ok(json::List(values)) => {
+                    assert_eq!(values.len(), 1);
+                    assert_eq!(values[0].find(&"count".to_string()).and_then(|count| count.as_i64()),
+                               Some(1));
+                },
+                Ok(_) => panic!("a list of emitted values was expected")
+
+            }
+
+            match graph.exec("g.V(\"Casablanca\").Out(".to_string()) {
+
+                Err(ServerError(status, message, query)) => {
+                    assert!(status != 200);
+                    assert!(message.len() > 0);
+                    assert_eq!(query.as_slice(), "g.V(\"Casablanca\").Out(");
+                },
+                Err(error) => panic!(error.to_string()),
+                Ok(_) => panic!("the broken query should be rejected")
+
+            }
+
+            match graph.shape(Vertex::start(Node("Casablanca")).All()) {
+
+                Err(error) => panic!(error.to_string()),
+                Ok(shape) => {
+                    assert!(shape.nodes.len() > 0);
+                    assert!(shape.nodes.iter().any(|node| node.values.contains(&"Casablanca".to_string())));
+                }
+
+            }
+
+            match graph.find_mql(&Mql::new(mql::object(vec!(("id", mql::any()),
+                                                            ("name", mql::value("Casablanca")))))) {
+
+                Err(error) => panic!(error.to_string()),
+                Ok(json::List(movies)) => {
+                    assert_eq!(movies.len(), 1);
+                    assert_eq!(movies[0].find(&"id".to_string()).and_then(|id| id.as_string()),
+                               Some("/en/casablanca_1942"));
+                },
+                Ok(_) => panic!("a list of filled templates was expected")
+
+            }
+
+            let new_movie = [Quad::new("/zh/new_movie", "name", "New Movie")];
+
+            match graph.write(new_movie.as_slice()) {
+                Err(error) => panic!(error.to_string()),
+                Ok(Refused(message)) => panic!(message),
+                Ok(Written(_)) => ()
+            }
+
+            match graph.find(Vertex::start(Node("New Movie")).All()) {
+
+                Err(error) => panic!(error.to_string()),
+                Ok(NodeMaps(GraphNodes(nodes))) => {
+                    assert_eq!(nodes.len(), 1);
+                    match nodes.iter().next() {
+                        Some(&GraphNode(ref new_movie)) => {
+                            assert_eq!(new_movie["id".to_string()].as_slice(), "New Movie");
+                        },
+                        None => panic!("first node was not found")
+                    }
+                },
+                Ok(_) => panic!("tag maps were expected")
+
+            }
+
+            match graph.delete(new_movie.as_slice()) {
+                Err(error) => panic!(error.to_string()),
+                Ok(Refused(message)) => panic!(message),
+                Ok(Written(_)) => ()
             }
 
         }
 
     }
 
-    // it('test Emit', function(done) {
-    //     this.timeout(10000);
-    //     g.V("Casablanca").ForEach(function(d) { g.Emit(d); }, function(err, result) {
-    //         assert(result.length);
-    //         done(err);
-    //     });
-    // });
-    //
-    // it('test type shape', function(done) {
-    //     this.timeout(10000);
-    //     var graph = g.type('shape');
-    //     graph.V("Casablanca").All(function(err, result) {
-    //         assert(!result.links && result.nodes);
-    //         done(err);
-    //     });
-    // });
-    //
-    // it('test write', function(done)  {
-    //     client.write([{
-    //         subject: "/zh/new_movie",
-    //         predicate: "name",
-    //         object: "New Movie"
-    //     }], function(err) {
-    //         if(err) return done(err);
-    //         g.V('New Movie').All(function(err, result) {
-    //             if(err) return done(err);
-    //             assert.equal(result.length, 1);
-    //             assert.equal(result[0].id, "New Movie");
-    //             client.delete([{
-    //                 subject: "/zh/new_movie",
-    //                 predicate: "name",
-    //                 object: "New Movie"
-    //             }], function(err) {
-    //                 if(err) return done(err);
-    //                 g.V('New Movie').All(function(err, result) {
-    //                     assert.equal(result.length, 1);
-    //                     assert.equal(result[0].id, "");
-    //                     done(err);
-    //                 });
-    //             });
-    //         });
-    //     });
-    // });
-
 }
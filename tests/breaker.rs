@@ -0,0 +1,97 @@
+extern crate cayley;
+
+use std::io::timer;
+use std::time::Duration;
+
+use cayley::errors::{GraphResult, GraphRequestError};
+use cayley::errors::{ServerError, CircuitOpen, Unavailable};
+use cayley::breaker::{CircuitBreaker, Closed, Open, HalfOpen};
+
+use support::refused;
+
+mod support;
+
+static QUERY_URL: &'static str = "http://localhost:64210/api/v1/query/gremlin";
+
+fn rejected() -> GraphResult<()> {
+    Err(ServerError(400, "syntax error".to_string(), "g.V(".to_string()))
+}
+
+fn is_circuit_open(result: GraphResult<()>) -> bool {
+    match result {
+        Err(CircuitOpen(_)) => true,
+        _ => false
+    }
+}
+
+#[test]
+fn main() {
+
+    // == Opening ==
+
+    let breaker = CircuitBreaker::new(2, Duration::seconds(60));
+
+    assert!(breaker.call(QUERY_URL, || -> GraphResult<()> { Err(refused()) }).is_err());
+    assert_eq!(breaker.state(), Closed(1));
+    // the rejected query tells that the server is alive
+    assert!(breaker.call(QUERY_URL, || rejected()).is_err());
+    assert_eq!(breaker.state(), Closed(0));
+
+    assert!(breaker.call(QUERY_URL, || -> GraphResult<()> { Err(refused()) }).is_err());
+    assert!(breaker.call(QUERY_URL, || -> GraphResult<()> { Err(refused()) }).is_err());
+    match breaker.state() {
+        Open(_) => (),
+        state => panic!("the breaker should be open, not {}", state)
+    }
+
+    // == Failing fast ==
+
+    let mut called = false;
+    assert!(is_circuit_open(breaker.call(QUERY_URL, || { called = true; Ok(()) })));
+    assert!(!called);
+
+    // clones share the state
+    assert!(is_circuit_open(breaker.clone().call(QUERY_URL, || Ok(()))));
+
+    let error: GraphRequestError = CircuitOpen(QUERY_URL.to_string());
+    assert_eq!(error.kind(), Unavailable);
+    assert!(!error.is_retryable());
+    assert_eq!(error.to_string(), format!("Url(\"{}\"): Circuit is open, Cayley is not requested",
+                                          QUERY_URL));
+
+    // == Half-open ==
+
+    let breaker = CircuitBreaker::new(1, Duration::milliseconds(50));
+
+    assert!(breaker.call(QUERY_URL, || -> GraphResult<()> { Err(refused()) }).is_err());
+    timer::sleep(Duration::milliseconds(60));
+    // the probe fails, so the breaker is open once again
+    assert!(breaker.call(QUERY_URL, || -> GraphResult<()> {
+        match breaker.state() {
+            HalfOpen(_) => (),
+            state => panic!("the breaker should be half-open, not {}", state)
+        }
+        Err(refused())
+    }).is_err());
+    timer::sleep(Duration::milliseconds(60));
+    // the probe succeeds, so the breaker is closed
+    assert!(breaker.call(QUERY_URL, || {
+        // no other request is let through while the server is probed
+        assert!(is_circuit_open(breaker.call(QUERY_URL, || Ok(()))));
+        Ok(())
+    }).is_ok());
+    assert_eq!(breaker.state(), Closed(0));
+
+    // == Lost probe ==
+
+    assert!(breaker.call(QUERY_URL, || -> GraphResult<()> { Err(refused()) }).is_err());
+    timer::sleep(Duration::milliseconds(60));
+    assert!(breaker.call(QUERY_URL, || {
+        // the probe is not answered for one more cool-down, so the next request probes instead
+        timer::sleep(Duration::milliseconds(60));
+        assert!(breaker.call(QUERY_URL, || Ok(())).is_ok());
+        assert_eq!(breaker.state(), Closed(0));
+        Ok(())
+    }).is_ok());
+
+}
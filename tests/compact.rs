@@ -0,0 +1,69 @@
+extern crate cayley;
+
+use cayley::GraphNode;
+use cayley::compact::CompactNodes;
+use cayley::errors::ResponseParseFailed;
+
+#[test]
+fn main() {
+
+    // == All() ==
+
+    let nodes = CompactNodes::from_json("{\"result\": [\
+                                            {\"id\": \"/en/casablanca_1942\", \"name\": \"Casablanca\"},\
+                                            {\"id\": \"/en/alien\", \"name\": \"Alien\", \"year\": 1979}\
+                                         ]}").unwrap();
+
+    assert_eq!(nodes.len(), 2);
+    assert_eq!(nodes.keys(), vec!("id", "name", "year"));
+
+    let casablanca = nodes.get(0).unwrap();
+    assert_eq!(casablanca.get("id"), Some("/en/casablanca_1942"));
+    assert_eq!(casablanca.get("name"), Some("Casablanca"));
+    assert_eq!(casablanca.get("year"), None);
+    assert_eq!(casablanca.get("director"), None);
+
+    let alien = nodes.get(1).unwrap();
+    assert_eq!(alien.len(), 3);
+    assert_eq!(alien.get("year"), Some("1979"));
+    assert_eq!(alien.tags().collect::<Vec<(&str, &str)>>(),
+               vec!(("id", "/en/alien"), ("name", "Alien"), ("year", "1979")));
+
+    match alien.to_node() {
+        GraphNode(tags) => assert_eq!(tags["name".to_string()].as_slice(), "Alien")
+    }
+
+    assert!(nodes.get(2).is_none());
+    assert_eq!(nodes.iter().map(|node| node.get("name").unwrap()).collect::<Vec<&str>>(),
+               vec!("Casablanca", "Alien"));
+
+    // == Other finalizers ==
+
+    // .ToArray()
+    let ids = CompactNodes::from_json("{\"result\": [[\"/en/alien\", \"/en/aliens\"]]}").unwrap();
+    assert_eq!(ids.iter().map(|node| node.get("id").unwrap()).collect::<Vec<&str>>(),
+               vec!("/en/alien", "/en/aliens"));
+
+    // .TagArray()
+    let tagged = CompactNodes::from_json("{\"result\": [[{\"id\": \"/en/alien\"}]]}").unwrap();
+    assert_eq!(tagged.len(), 1);
+
+    // nothing found, `null` if the start node is not in the database at all
+    let null = CompactNodes::from_json("{\"result\": null}").unwrap();
+    assert!(null.is_empty());
+    assert!(null.is_null());
+    let empty = CompactNodes::from_json("{\"result\": []}").unwrap();
+    assert!(empty.is_empty());
+    assert!(!empty.is_null());
+    // a `null` value inside the result is not the `null` result
+    assert!(!CompactNodes::from_json("{\"result\": [null]}").unwrap().is_null());
+
+    // == Errors ==
+
+    assert!(CompactNodes::from_json("{\"result\": [{\"id\": ").is_err());
+    match CompactNodes::from_json("{\"result\": [{\"id\": {\"nested\": \"value\"}}]}") {
+        Err(ResponseParseFailed) => (),
+        _ => panic!("nested objects should not be read as nodes")
+    }
+
+}
@@ -2,7 +2,7 @@ extern crate cayley;
 
 use cayley::{Graph, V1};
 
-use cayley::{GraphNode, GraphNodes};
+use cayley::{GraphNode, GraphNodes, NodeMaps};
 
 use cayley::path::{Vertex, Query};
 use cayley::selector::AnyNode;
@@ -20,7 +20,7 @@ fn main() {
     match graph.find(Vertex::start(AnyNode).All()) {
 
         Err(error) => panic!(error.to_string()),
-        Ok(GraphNodes(nodes)) => {
+        Ok(NodeMaps(GraphNodes(nodes))) => {
             assert!(nodes.len() > 0);
             match nodes.iter().next() {
                 Some(&GraphNode(ref first_node)) => {
@@ -29,7 +29,8 @@ fn main() {
                 },
                 None => panic!("first node was not found")
             }
-        }
+        },
+        Ok(_) => panic!("tag maps were expected")
 
     };
 
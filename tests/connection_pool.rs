@@ -0,0 +1,99 @@
+extern crate cayley;
+extern crate http;
+extern crate url;
+
+use std::io;
+use std::time::Duration;
+use std::default::Default;
+
+use url::Url;
+use http::headers::content_type::MediaType;
+
+use cayley::GraphConfig;
+use cayley::connection::{ConnectionPool, Response};
+
+use support::{FakeCayley, Request, Reply, Answer, AnswerAndClose, Close, Hang, http_response};
+
+mod support;
+
+fn url(port: u16, path: &str) -> Url {
+    Url::parse(format!("http://127.0.0.1:{:u}{:s}", port, path).as_slice()).unwrap()
+}
+
+fn read_body(response: Response) -> String {
+    let Response { status, body: mut body } = response;
+    assert_eq!(status, 200);
+    String::from_utf8(body.read_to_end().unwrap()).unwrap()
+}
+
+fn reply(request: &Request) -> Reply {
+    match request.body.as_slice() {
+        "g.V().All()" => Answer(http_response("200 OK", "first")),
+        // the first connection serves two requests and is closed after them
+        "g.V()" => AnswerAndClose("HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n\
+                                   5\r\nsecon\r\n1;ext=1\r\nd\r\n0\r\n\r\n".to_string()),
+        // so the third request is sent through the new one
+        "[]" => Answer(http_response("200 OK", "third")),
+        // the request which may not be resent is read, and the connection is closed
+        _ => Close
+    }
+}
+
+// the request is read, but never answered
+fn silent(_: &Request) -> Reply { Hang }
+
+#[test]
+fn main() {
+
+    let server = FakeCayley::start(reply);
+    let port = server.port;
+
+    let pool = ConnectionPool::new(GraphConfig { max_idle: 2, ..Default::default() });
+    let text_plain = MediaType { type_: "text".to_string(), subtype: "plain".to_string(),
+                                 parameters: Vec::new() };
+
+    // == Keep-alive ==
+
+    let request = pool.prepare(url(port, "/api/v1/query/gremlin")).unwrap();
+    assert_eq!(read_body(request.post(None, b"g.V().All()", true).unwrap()),
+               "first".to_string());
+    assert_eq!(server.requests.recv().body, "g.V().All()".to_string());
+    assert_eq!(server.connections(), 1);
+
+    // == Chunked body ==
+
+    let request = pool.prepare(url(port, "/api/v1/query/gremlin")).unwrap();
+    assert_eq!(read_body(request.post(Some(text_plain), b"g.V()", true).unwrap()),
+               "second".to_string());
+    assert_eq!(server.requests.recv().body, "g.V()".to_string());
+    assert_eq!(server.connections(), 1);
+
+    // == Stale connection ==
+
+    let request = pool.prepare(url(port, "/api/v1/write")).unwrap();
+    assert_eq!(read_body(request.post(None, b"[]", true).unwrap()),
+               "third".to_string());
+    assert_eq!(server.requests.recv().body, "[]".to_string());
+    assert_eq!(server.connections(), 2);
+
+    // == Closed after the request was sent ==
+
+    let request = pool.prepare(url(port, "/api/v1/write")).unwrap();
+    assert!(request.post(None, b"[{}]", false).is_err());
+    assert_eq!(server.requests.recv().body, "[{}]".to_string());
+    // not resent through a new connection
+    assert_eq!(server.connections(), 2);
+
+    // == Timeout ==
+
+    let server = FakeCayley::start(silent);
+
+    let pool = ConnectionPool::new(GraphConfig { read_timeout: Some(Duration::milliseconds(100)),
+                                                 ..Default::default() });
+    let request = pool.prepare(url(server.port, "/api/v1/query/gremlin")).unwrap();
+    match request.post(None, b"g.V().All()", true) {
+        Err(error) => assert_eq!(error.kind, io::TimedOut),
+        Ok(_) => panic!("the request should time out")
+    }
+
+}
@@ -0,0 +1,83 @@
+#![feature(macro_rules)]
+
+extern crate serialize;
+extern crate cayley;
+
+use std::collections::HashMap;
+
+use cayley::GraphNode;
+use cayley::decode;
+use cayley::decode::{MissingTag, InvalidValue, UnsupportedType};
+
+#[deriving(Decodable, PartialEq, Show)]
+struct Film {
+    id: String,
+    year: int,
+    rating: f64,
+    is_colored: bool,
+    sequel: Option<String>
+}
+
+#[deriving(Decodable, PartialEq, Show)]
+struct Actor {
+    id: String,
+    films: Vec<String>
+}
+
+fn node(tags: Vec<(&str, &str)>) -> GraphNode {
+    let mut map = HashMap::new();
+    for &(tag, value) in tags.iter() {
+        map.insert(tag.to_string(), value.to_string());
+    }
+    GraphNode(map)
+}
+
+#[test]
+fn main() {
+
+    macro_rules! decode_fail(
+        ($node:expr, $error:expr) => (
+            match decode::from_node::<Film>($node) {
+                Ok(film) => panic!("{} should not be decoded", film),
+                Err(error) => assert_eq!(error, $error)
+            }
+        );
+    )
+
+    // == Structures ==
+
+    assert_eq!(decode::from_node::<Film>(node(vec!(("id", "Casablanca"), ("year", "1942"),
+                                                   ("rating", "8.5"), ("is_colored", "false"),
+                                                   ("director", "Michael Curtiz")))),
+               Ok(Film { id: "Casablanca".to_string(), year: 1942, rating: 8.5,
+                         is_colored: false, sequel: None }));
+
+    assert_eq!(decode::from_node::<Film>(node(vec!(("id", "Alien"), ("year", "1979"),
+                                                   ("rating", "8"), ("is_colored", "true"),
+                                                   ("sequel", "Aliens")))).map(|film| film.sequel),
+               Ok(Some("Aliens".to_string())));
+
+    // == Maps ==
+
+    let years: HashMap<String, int> =
+        decode::from_node(node(vec!(("Casablanca", "1942"), ("Alien", "1979")))).unwrap();
+    assert_eq!(years.len(), 2);
+    assert_eq!(years["Alien".to_string()], 1979);
+
+    // == Errors ==
+
+    decode_fail!(node(vec!(("id", "Casablanca"), ("rating", "8.5"), ("is_colored", "false"))),
+                 MissingTag("year".to_string()));
+    decode_fail!(node(vec!(("id", "Casablanca"), ("year", "nineteen"),
+                           ("rating", "8.5"), ("is_colored", "false"))),
+                 InvalidValue("year".to_string(), "nineteen".to_string(), "int".to_string()));
+    decode_fail!(node(vec!(("id", "Casablanca"), ("year", "1942"),
+                           ("rating", "8.5"), ("is_colored", "no"))),
+                 InvalidValue("is_colored".to_string(), "no".to_string(), "bool".to_string()));
+
+    match decode::from_node::<Actor>(node(vec!(("id", "Humphrey Bogart"), ("films", "Casablanca")))) {
+        Ok(actor) => panic!("{} should not be decoded", actor),
+        Err(error) => assert_eq!(error, UnsupportedType("films".to_string()))
+    }
+
+}
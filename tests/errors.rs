@@ -0,0 +1,61 @@
+extern crate cayley;
+
+use std::io;
+use std::error::Error;
+
+use cayley::errors::{RequestFailed, ReadFailed, ResponseParseFailed, QueryBuildFailed, ServerError,
+                     NodeDecodingFailed, Timeout};
+use cayley::errors::request_failed;
+use cayley::errors::{NetworkError, TimedOut, InvalidRequest, RejectedByServer, InvalidResponse};
+use cayley::decode::MissingTag;
+
+use support::{io_error, reset, refused};
+
+mod support;
+
+#[test]
+fn main() {
+
+    // == Kinds ==
+
+    assert_eq!(refused().kind(), NetworkError);
+    assert_eq!(RequestFailed(io_error(io::TimedOut), "g.V().All()".to_string()).kind(), TimedOut);
+    assert_eq!(ReadFailed(io_error(io::FileNotFound), "movies.nq".to_string()).kind(), InvalidRequest);
+    assert_eq!(QueryBuildFailed(vec!()).kind(), InvalidRequest);
+    assert_eq!(ServerError(400, "syntax error".to_string(), "g.V(".to_string()).kind(),
+               RejectedByServer);
+    assert_eq!(ResponseParseFailed.kind(), InvalidResponse);
+    assert_eq!(Timeout("g.V().All()".to_string()).kind(), TimedOut);
+
+    // == Timeouts ==
+
+    match request_failed(io_error(io::TimedOut), "g.V().All()".to_string()) {
+        Timeout(query) => assert_eq!(query.as_slice(), "g.V().All()"),
+        _ => panic!("the timed out request should be reported as Timeout")
+    }
+    match request_failed(io_error(io::ConnectionReset), "g.V().All()".to_string()) {
+        RequestFailed(error, _) => assert_eq!(error.kind, io::ConnectionReset),
+        _ => panic!("only timeouts should be reported as Timeout")
+    }
+
+    // == Retries ==
+
+    assert!(reset().is_retryable());
+    assert!(RequestFailed(io_error(io::TimedOut), "g.V().All()".to_string()).is_retryable());
+    assert!(ServerError(503, "unavailable".to_string(), "g.V().All()".to_string()).is_retryable());
+    assert!(!ServerError(400, "syntax error".to_string(), "g.V(".to_string()).is_retryable());
+    assert!(!ResponseParseFailed.is_retryable());
+    assert!(Timeout("g.V().All()".to_string()).is_retryable());
+
+    // == Causes ==
+
+    let failed = refused();
+    assert_eq!(failed.description(), "request failed");
+    assert_eq!(failed.cause().map(|cause| cause.description()), Some("failed"));
+
+    let decoding_failed = NodeDecodingFailed(MissingTag("year".to_string()));
+    assert_eq!(decoding_failed.cause().map(|cause| cause.description()), Some("missing tag"));
+
+    assert!(ResponseParseFailed.cause().is_none());
+
+}
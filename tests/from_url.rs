@@ -0,0 +1,47 @@
+extern crate url;
+extern crate cayley;
+
+use cayley::{Graph, DefaultVersion, V2};
+use cayley::errors::InvalidUrl;
+
+fn base_url(url: &str) -> String {
+    match Graph::from_url(url, DefaultVersion) {
+        Ok(graph) => graph.base_url().to_string(),
+        Err(error) => panic!("{} should be accepted, but failed with {}", url, error)
+    }
+}
+
+#[test]
+fn main() {
+
+    // == Schemes ==
+
+    match Graph::from_url("ftp://example.com/cayley", DefaultVersion) {
+        Err(InvalidUrl(url::InvalidScheme, source)) =>
+            assert_eq!(source.as_slice(), "ftp://example.com/cayley"),
+        _ => panic!("only http and https URLs should be accepted")
+    }
+    match Graph::from_url("example.com:64210", V2) {
+        Err(InvalidUrl(..)) => (),
+        _ => panic!("the URL without a scheme should not be accepted")
+    }
+
+    // == Ports ==
+
+    assert_eq!(base_url("http://example.com"), "http://example.com:80".to_string());
+    assert_eq!(base_url("https://example.com"), "https://example.com:443".to_string());
+    assert_eq!(base_url("http://localhost:64210"), "http://localhost:64210".to_string());
+    assert_eq!(base_url("https://example.com:8443/"), "https://example.com:8443".to_string());
+
+    // == Path prefix ==
+
+    assert_eq!(base_url("http://example.com:8080/cayley"), "http://example.com:8080/cayley".to_string());
+    assert_eq!(base_url("http://example.com:8080/cayley/"), "http://example.com:8080/cayley".to_string());
+    assert_eq!(base_url("http://example.com/db/cayley/"), "http://example.com:80/db/cayley".to_string());
+    assert_eq!(base_url("http://example.com//cayley//"), "http://example.com:80/cayley".to_string());
+
+    // the same as the Graph created from the host and the port
+    assert_eq!(Graph::new("localhost", 64210, DefaultVersion).unwrap().base_url().to_string(),
+               base_url("http://localhost:64210/"));
+
+}
@@ -0,0 +1,33 @@
+extern crate serialize;
+extern crate cayley;
+
+use serialize::json;
+
+use cayley::mql;
+use cayley::mql::Mql;
+use cayley::errors::DecodingFailed;
+
+#[test]
+fn main() {
+
+    // == Compile ==
+
+    let query = Mql::new(json::List(vec!(mql::object(vec!(("id", mql::any()),
+                                                          ("name", mql::value("Casablanca")),
+                                                          ("/film/film/starring", mql::all()))))));
+    assert_eq!(query.compile(),
+               "[{\"/film/film/starring\":[],\"id\":null,\"name\":\"Casablanca\"}]".to_string());
+
+    // == From string ==
+
+    let parsed = Mql::from_str("[{\"id\": null, \"name\": \"Casablanca\",\
+                                  \"/film/film/starring\": []}]").unwrap();
+    assert_eq!(parsed, query);
+    assert_eq!(parsed.compile(), query.compile());
+
+    match Mql::from_str("[{\"id\": null") {
+        Err(DecodingFailed(_, source)) => assert_eq!(source.as_slice(), "[{\"id\": null"),
+        _ => panic!("the broken template should not be parsed")
+    }
+
+}
@@ -0,0 +1,92 @@
+extern crate cayley;
+
+use cayley::{Graph, DefaultVersion, GraphNodes};
+use cayley::{QueryResult, NodeMaps, NodeArray, NodeValue, NodeMap, Emitted, NullResult};
+use cayley::path::{Vertex, Query, FinalizedQuery};
+use cayley::selector::Node;
+
+use support::{FakeCayley, Request, Reply, Answer, http_response};
+
+mod support;
+
+// `null` for the queries which start from a node missing in the database, like Cayley does
+fn reply(request: &Request) -> Reply {
+    match request.body.as_slice().contains("<nobody>") {
+        true => Answer(http_response("200 OK", "{\"result\": null}")),
+        false => Answer(http_response("200 OK", "{\"result\": []}"))
+    }
+}
+
+static FINALIZERS: [&'static str, ..7] = ["All", "GetLimit", "ToArray", "ToValue",
+                                           "TagArray", "TagValue", "ForEach"];
+
+fn finalized(finalizer: &str, start: &str) -> FinalizedQuery {
+    let path = Vertex::start(Node(start));
+    match finalizer {
+        "All" => path.All(),
+        "GetLimit" => path.GetLimit(5),
+        "ToArray" => path.ToArray(),
+        "ToValue" => path.ToValue(),
+        "TagArray" => path.TagArray(),
+        "TagValue" => path.TagValue(),
+        _ => path.ForEach("g.Emit(d.id)")
+    }
+}
+
+fn is_empty(result: &QueryResult) -> bool {
+    match *result {
+        NodeMaps(GraphNodes(ref nodes)) => nodes.is_empty(),
+        NodeArray(ref ids) => ids.is_empty(),
+        NodeValue(ref id) => id.is_none(),
+        NodeMap(ref node) => node.is_none(),
+        Emitted(ref values) => values.is_empty(),
+        NullResult => false
+    }
+}
+
+#[test]
+fn main() {
+
+    let server = FakeCayley::start(reply);
+
+    let graph = Graph::new("127.0.0.1", server.port, DefaultVersion).unwrap();
+
+    // == Every finalizer ==
+
+    for name in FINALIZERS.iter() {
+        match graph.find(finalized(*name, "<nobody>")) {
+            Ok(NullResult) => (),
+            _ => panic!("`null` result of .{}() should be NullResult", name)
+        }
+        match graph.find(finalized(*name, "<alice>")) {
+            Ok(ref result) if is_empty(result) => (),
+            _ => panic!("empty result of .{}() should be an empty result, not NullResult", name)
+        }
+    }
+
+    // == Raw queries ==
+
+    match graph.exec("g.V(\"<nobody>\").All()".to_string()) {
+        Ok(NullResult) => (),
+        _ => panic!("`null` result of exec should be NullResult")
+    }
+    match graph.exec("g.V(\"<alice>\").All()".to_string()) {
+        Ok(NodeMaps(GraphNodes(nodes))) => assert!(nodes.is_empty()),
+        _ => panic!("empty result of exec should be NodeMaps")
+    }
+
+    // == Compact nodes and iterator ==
+
+    let null = graph.find_compact(Vertex::start(Node("<nobody>")).All()).unwrap();
+    assert!(null.is_empty() && null.is_null());
+    let empty = graph.find_compact(Vertex::start(Node("<alice>")).All()).unwrap();
+    assert!(empty.is_empty() && !empty.is_null());
+
+    let mut null = graph.find_iter(Vertex::start(Node("<nobody>")).All()).unwrap();
+    assert!(null.next().is_none());
+    assert!(null.is_null());
+    let mut empty = graph.find_iter(Vertex::start(Node("<alice>")).All()).unwrap();
+    assert!(empty.next().is_none());
+    assert!(!empty.is_null());
+
+}
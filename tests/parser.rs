@@ -0,0 +1,138 @@
+#![feature(macro_rules)]
+
+extern crate cayley;
+
+use cayley::parser;
+
+use cayley::path::Vertex as V;
+use cayley::path::Morphism as M;
+
+use cayley::path::Compile; // required to use .compile() method
+use cayley::path::Path; // required to be able to use Path methods such as .In, .Out, ...
+use cayley::path::Query; // required to be able to use Query methods such as .All, .GetLimit, ...
+
+use cayley::selector::{AnyNode, Node, Nodes};
+use cayley::selector::{AnyTag, Tag, Tags};
+use cayley::selector::{AnyPredicate, Predicate, Predicates, Query};
+
+#[test]
+#[allow(non_snake_case)]
+fn main() {
+
+    // parsing the compiled query gives the same query back
+    macro_rules! round_trip(
+        ($src:expr) => ({
+            let compiled = $src.compile().unwrap();
+            match parser::parse(compiled.as_slice()) {
+                Ok(parsed) => assert_eq!(parsed.compile().ok(), Some(compiled.clone())),
+                Err(error) => panic!("{}: {}", compiled, error)
+            }
+        });
+    )
+
+    // the same for the paths which are not finalized
+    macro_rules! round_trip_path(
+        ($src:expr) => ({
+            let compiled = $src.compile().unwrap();
+            match parser::parse_path(compiled.as_slice()) {
+                Ok(parsed) => assert_eq!(parsed.compile().ok(), Some(compiled.clone())),
+                Err(error) => panic!("{}: {}", compiled, error)
+            }
+        });
+    )
+
+    macro_rules! parse_eq(
+        ($src:expr, $res:expr) => (
+            match parser::parse($src) {
+                Ok(parsed) => assert_eq!(parsed.compile().ok(), Some($res.to_string())),
+                Err(error) => panic!("{}: {}", $src, error)
+            }
+        );
+    )
+
+    macro_rules! parse_fail(
+        ($src:expr, $line:expr, $column:expr) => (
+            match parser::parse($src) {
+                Ok(parsed) => panic!("{} should not be parsed, got {}", $src, parsed.compile().ok()),
+                Err(error) => assert_eq!((error.line, error.column), ($line, $column))
+            }
+        );
+    )
+
+    // == Round trip ==
+
+    round_trip_path!(V::start(AnyNode));
+    round_trip!(V::start(Node("foo")).All());
+    round_trip!(V::start(Nodes(vec!("foo", "bar"))).GetLimit(5));
+
+    round_trip_path!(V::start(Node("C")).Out(Predicate("follows"), AnyTag).In(AnyPredicate, Tag("t")));
+    round_trip_path!(V::start(Node("D")).Out(Predicates(vec!("follows", "status")), Tags(vec!("a", "b"))));
+    round_trip_path!(V::start(Node("D")).Out(Query(&V::start(Node("status"))), Tag("pred")));
+    round_trip_path!(V::start(Node("F")).Both(AnyPredicate, AnyTag).Is(Nodes(vec!("B", "C"))));
+    round_trip_path!(V::start(AnyNode).Has(Predicate("follows"), Node("B")).As(Tag("start"))
+                                      .Back(Tags(vec!("start", "end"))));
+    round_trip_path!(V::start(Nodes(vec!("D", "B"))).Save(Predicate("follows"), Tag("target")));
+
+    let mut dFollows = V::start(Node("D")); dFollows.Out(Predicate("follows"), AnyTag);
+    round_trip!(V::start(Node("C")).Out(Predicate("follows"), AnyTag).And(&dFollows).All());
+    round_trip!(V::start(Node("C")).Out(Predicate("follows"), AnyTag).Or(&dFollows).ToArray());
+
+    let mut friendOfFriend = M::start("friendOfFriend");
+            friendOfFriend.Out(Predicate("follows"), AnyTag)
+                          .Out(Predicate("follows"), AnyTag);
+    round_trip_path!(V::start(Node("C")).Follow(&friendOfFriend).Has(Predicate("status"), Node("cool_person")));
+    round_trip!(V::start(AnyNode).FollowR(&friendOfFriend).TagValue());
+
+    round_trip!(V::start(Node("C")).ToValue());
+    round_trip!(V::start(Node("C")).TagArray());
+    round_trip!(V::start(Node("C")).ForEach("g.Emit({ id: d.id, \"name\": d[\"}\"] })"));
+    round_trip!(V::start(Node("C")).ForEachLimit(5, "g.Emit(d.id)"));
+
+    round_trip_path!(V::start(Node("He said \"hi\"\n\t\\")).As(Tag(" \x07")));
+    round_trip_path!(V::start(Node("Zoë")).Has(Predicate("名前"), Node("Амели")));
+
+    // == Legacy queries ==
+
+    parse_eq!("graph.Vertex('C').Out('follows')  .All();",
+              "g.V(\"C\").Out(\"follows\").All()");
+
+    parse_eq!("// friends\ng.V(\"C\")\n  .Out(null, \"tag\") /* tagged */\n  .Tag(\"a\", \"b\")\n  .GetLimit(-1)",
+              "g.V(\"C\").Out(null,\"tag\").As(\"a\",\"b\").GetLimit(-1)");
+
+    parse_eq!("var q = g.V(\"D\").Out(\"follows\"); g.V(\"C\").Intersect(q).Union(q).All()",
+              "g.V(\"C\").And(g.V(\"D\").Out(\"follows\")).Or(g.V(\"D\").Out(\"follows\")).All()");
+
+    parse_eq!("var m = g.Morphism().Out(\"a\");\nvar n = g.M().In(\"b\");\ng.V().Follow(n).Map(function(d) {g.Emit(d)})",
+              "var n = g.M().In(\"b\");g.V().Follow(n).ForEach(function(d) { g.Emit(d) })");
+
+    parse_eq!("g.V(\"\\u0041\\q\").All()", "g.V(\"Aq\").All()");
+
+    match parser::parse_morphism("fof", "g.M().Out(\"follows\").Out(\"follows\")") {
+        Ok(morphism) => assert_eq!(morphism.compile().ok(), friendOfFriend.compile().ok()),
+        Err(error) => panic!(error.to_string())
+    }
+
+    // == Errors ==
+
+    parse_fail!("", 1, 1);
+    parse_fail!("g.V(\"C\").Skip(5).All()", 1, 10);
+    parse_fail!("g.V(\"C\")\n  .Out(\"follows\"\n  .All()", 3, 3);
+    parse_fail!("g.V(\"C).All()", 1, 5);
+    parse_fail!("g.V(C).All()", 1, 5);
+    parse_fail!("g.V().Follow(unknown).All()", 1, 14);
+    parse_fail!("g.V().All(); g.V().All()", 1, 14);
+    parse_fail!("g.V().ForEach(function(x) { g.Emit(x) })", 1, 24);
+    parse_fail!("g.V().ForEach(function(d) { g.Emit(d) )", 1, 28);
+    parse_fail!("g.M().Out(\"follows\")", 1, 3);
+    parse_fail!("var m = g.M().All(); g.V()", 1, 15);
+    parse_fail!("g.V().All().Out(\"follows\")", 1, 13);
+    parse_fail!("g.V().GetLimit(5).All()", 1, 19);
+    parse_fail!("g.V(\"C\").Out(\"follows\")", 1, 24);
+    parse_fail!("g.V().And(g.V().All()).All()", 1, 17);
+
+    match parser::parse_path("g.V().Out(\"follows\").All()") {
+        Ok(_) => panic!("a finalized query should not be parsed as a path"),
+        Err(error) => assert_eq!((error.line, error.column), (1, 22))
+    }
+
+}
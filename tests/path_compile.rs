@@ -10,26 +10,38 @@ use cayley::path::Morphism as M;
 use cayley::path::Compile; // required to use .compile() method
 use cayley::path::Path; // required to be able to use Path methods such as .In, .Out, ...
 use cayley::path::Query; // required to be able to use Query methods such as .All, .GetLimit, ...
+use cayley::path::Reuse; // required to use .save_as() method
 
 use cayley::selector::{AnyNode, Node, Nodes};
 use cayley::selector::{AnyTag, Tag, Tags};
 use cayley::selector::{AnyPredicate, Predicate, Predicates, Query};
 
+use cayley::step::{StartVertex, StartMorphism, Out, Has, And, Follow, FollowR, Final, All, GetLimit};
+use cayley::step::{Unset, Single, Multiple};
+
+use cayley::errors::QueryBuildFailed;
+use cayley::errors::{EmptyPath, NotStarted, StartedTwice, FinalizedTwice, StepAfterFinalizer,
+                     SubQueryFailed, InvalidName};
+
+use cayley::{Graph, DefaultVersion};
+use cayley::step::Traversal;
+use cayley::retry::RetryPolicy;
+
 #[test]
 #[allow(non_snake_case)]
 fn main() {
 
     macro_rules! path_eq(
         ($src:expr, $res:expr) => (
-            assert_eq!($src.compile(), Some($res.to_string()));
+            assert_eq!($src.compile().ok(), Some($res.to_string()));
         );
     )
 
     macro_rules! path_fail(
-        ($src:expr, $msg:ident) => (
+        ($src:expr, $msg:expr) => (
             match $src.compile() {
-                Some(_) => panic!($msg),
-                None => ()
+                Ok(_) => panic!($msg),
+                Err(_) => ()
             };
         );
     )
@@ -61,10 +73,10 @@ fn main() {
 
     match M::start("morph").Out(Predicate("foo"), AnyTag)
                            .Out(Predicate("bar"), AnyTag).compile() {
-        Some(result) => {
+        Ok(result) => {
             assert_eq!(result, "g.M().Out(\"foo\").Out(\"bar\")".to_string())
         }
-        None => panic!()
+        Err(error) => panic!(error.to_string())
     }
 
     path_eq!(M::start("morph").Out(Predicate("foo"), AnyTag)
@@ -168,8 +180,8 @@ fn main() {
 
     // path.Intersect / path.And
 
-    let mut cFollows = V::prepare(); cFollows.From(Node("C")).Out(Predicate("follows"), AnyTag);
-    let mut dFollows = V::prepare(); dFollows.From(Node("D")).Out(Predicate("follows"), AnyTag);
+    let mut cFollows = V::prepare().From(Node("C")); cFollows.Out(Predicate("follows"), AnyTag);
+    let mut dFollows = V::prepare().From(Node("D")); dFollows.Out(Predicate("follows"), AnyTag);
 
     path_eq!(cFollows.clone().Intersect(&dFollows),
              "g.V(\"C\").Out(\"follows\").And(g.V(\"D\").Out(\"follows\"))");
@@ -203,6 +215,18 @@ fn main() {
     path_eq!(V::start(AnyNode).Has(Predicate("status"), Node("cool_person")).FollowR(&friendOfFriend),
              "var friendOfFriend = g.M().Out(\"follows\").Out(\"follows\");g.V().Has(\"status\",\"cool_person\").FollowR(friendOfFriend)");
 
+    // the morphisms followed by the sub-queries and by the other morphisms are declared
+    // before the whole query
+
+    let mut cFriends = V::start(Node("C")); cFriends.Follow(&friendOfFriend);
+    path_eq!(V::start(Node("D")).And(&cFriends).All(),
+             "var friendOfFriend = g.M().Out(\"follows\").Out(\"follows\");g.V(\"D\").And(g.V(\"C\").Follow(friendOfFriend)).All()");
+
+    let mut thirdHand = M::start("thirdHand");
+            thirdHand.Follow(&friendOfFriend).Out(Predicate("follows"), AnyTag);
+    path_eq!(V::start(Node("C")).Follow(&thirdHand),
+             "var friendOfFriend = g.M().Out(\"follows\").Out(\"follows\");var thirdHand = g.M().Follow(friendOfFriend).Out(\"follows\");g.V(\"C\").Follow(thirdHand)");
+
     // == Query finals ==
 
     path_eq!(V::start(AnyNode).Out(Predicate("follows"), AnyTag).All(),
@@ -211,18 +235,179 @@ fn main() {
     path_eq!(V::start(Node("foo")).Out(Predicate("follows"), AnyTag).GetLimit(5),
              "g.V(\"foo\").Out(\"follows\").GetLimit(5)");
 
-    /* TODO:
-
     path_eq!(V::start(Node("bar")).In(Predicate("follows"), AnyTag).ToArray(),
-             "g.V(\"bar\").In(\"follows\").toArray()");
+             "g.Emit(g.V(\"bar\").In(\"follows\").ToArray())");
 
     path_eq!(V::start(AnyNode).Out(Predicate("follows"), AnyTag).ToValue(),
-             "g.V().Out(\"follows\").ToValue()");
+             "g.Emit(g.V().Out(\"follows\").ToValue())");
+
+    path_eq!(V::start(Node("foo")).Out(Predicate("follows"), AnyTag).TagArray(),
+             "g.Emit(g.V(\"foo\").Out(\"follows\").TagArray())");
 
     path_eq!(V::start(Node("foo")).Out(Predicate("follows"), AnyTag).TagValue(),
-             "g.V(\"foo\").Out(\"follows\").TagValue()");
+             "g.Emit(g.V(\"foo\").Out(\"follows\").TagValue())");
+
+    path_eq!(V::start(Node("C")).Follow(&friendOfFriend).ToArray(),
+             "var friendOfFriend = g.M().Out(\"follows\").Out(\"follows\");g.Emit(g.V(\"C\").Follow(friendOfFriend).ToArray())");
+
+    path_eq!(V::start(Node("C")).Out(Predicate("follows"), AnyTag).ForEach("g.Emit(d.id)"),
+             "g.V(\"C\").Out(\"follows\").ForEach(function(d) { g.Emit(d.id) })");
+
+    path_eq!(V::start(Node("C")).Out(Predicate("follows"), AnyTag).ForEachLimit(5, "g.Emit(d.id)"),
+             "g.V(\"C\").Out(\"follows\").ForEach(5, function(d) { g.Emit(d.id) })");
+
+    path_eq!(V::start(Node("C")).Map("g.Emit(d)"),
+             "g.V(\"C\").ForEach(function(d) { g.Emit(d) })");
+
+    // == Escaping ==
+
+    path_eq!(V::start(Node("He said \"hi\"")).All(),
+             "g.V(\"He said \\\"hi\\\"\").All()");
+
+    path_eq!(V::start(Node("C:\\temp")).Out(Predicate("back\\slash"), Tag("\\")),
+             "g.V(\"C:\\\\temp\").Out(\"back\\\\slash\",\"\\\\\")");
+
+    path_eq!(V::start(Nodes(vec!("line\nbreak", "carriage\rreturn", "tab\tbed"))),
+             "g.V(\"line\\nbreak\",\"carriage\\rreturn\",\"tab\\tbed\")");
+
+    path_eq!(V::start(AnyNode).Is(Node("bell\x07null\x00del\x7f")),
+             "g.V().Is(\"bell\\u0007null\\u0000del\\u007f\")");
+
+    path_eq!(V::start(AnyNode).As(Tag("\u2028\u2029")),
+             "g.V().As(\"\\u2028\\u2029\")");
+
+    path_eq!(V::start(Node("Zoë")).Has(Predicate("名前"), Node("Амели")),
+             "g.V(\"Zoë\").Has(\"名前\",\"Амели\")");
+
+    // an attempt to inject a query is kept inside the string
+    path_eq!(V::start(Node("x\")).All();g.V(\"y")).Back(Tags(vec!("a\"", "b"))),
+             "g.V(\"x\\\")).All();g.V(\\\"y\").Back(\"a\\\"\",\"b\")");
+
+    path_eq!(V::start(AnyNode).Has(Predicates(vec!("\"")), Nodes(vec!("\\"))),
+             "g.V().Has([\"\\\"\"],[\"\\\\\"])");
+
+    // == Steps ==
+
+    assert_eq!(V::start(Node("C")).OutP(Predicates(vec!("follows", "likes"))).All().traversal().steps,
+               vec!(StartVertex(Single("C".to_string())),
+                    Out(Multiple(vec!("follows".to_string(), "likes".to_string())), Unset),
+                    Final(All)));
+
+    let steps = V::start(AnyNode).Follow(&friendOfFriend).Has(Predicate("status"), AnyNode).traversal().clone();
+    assert_eq!(steps.includes, vec!(("friendOfFriend".to_string(), friendOfFriend.traversal().clone())));
+    assert_eq!(steps.steps, vec!(StartVertex(Unset),
+                                 Follow("friendOfFriend".to_string()),
+                                 Has(Single("status".to_string()), Unset)));
+
+    assert_eq!(friendOfFriend.traversal().steps[0], StartMorphism);
+
+    // == Build errors ==
+
+    // the typed API does not allow these mistakes, but the raw steps are checked the same way
+
+    match V::start(Node("C")).add_step(StartVertex(Single("D".to_string()))).All().compile() {
+        Err(QueryBuildFailed(errors)) =>
+            assert_eq!(errors, vec!(StartedTwice(StartVertex(Single("D".to_string()))))),
+        _ => panic!("should fail to start a path twice")
+    }
+
+    let finalized_twice = V::start(Node("C")).add_step(Final(All)).GetLimit(5);
+    match finalized_twice.compile() {
+        Err(QueryBuildFailed(errors)) => assert_eq!(errors, vec!(FinalizedTwice(Final(GetLimit(5))))),
+        _ => panic!("should fail to finalize a query twice")
+    }
+    // the raw finalizer does not confuse the query
+    assert_eq!(*finalized_twice.finalizer(), GetLimit(5));
+
+    match V::start(Node("C")).add_step(Final(All)).OutP(Predicate("follows")).compile() {
+        Err(QueryBuildFailed(errors)) =>
+            assert_eq!(errors, vec!(StepAfterFinalizer(Out(Single("follows".to_string()), Unset)))),
+        _ => panic!("should fail to add a step after the query is finalized")
+    }
+
+    let mut broken = V::start(Node("D")); broken.add_step(StartVertex(Unset));
+    match V::start(Node("C")).And(&broken).All().compile() {
+        Err(QueryBuildFailed(errors)) => match errors[0] {
+            SubQueryFailed(And(_), ref failed) =>
+                assert_eq!(*failed, vec!(StartedTwice(StartVertex(Unset)))),
+            _ => panic!("should report the failed sub-query")
+        },
+        _ => panic!("should fail to intersect with a broken query")
+    }
+
+    path_fail!(V::start(AnyNode).Out(Query(&broken), AnyTag),
+               "should fail to take predicates from a broken query");
+
+    // the raw traversals may be not started at all
+    let mut unstarted = Traversal::new();
+    unstarted.push(Out(Single("follows".to_string()), Unset));
+    match unstarted.compile() {
+        Err(QueryBuildFailed(errors)) =>
+            assert_eq!(errors, vec!(NotStarted(Out(Single("follows".to_string()), Unset)))),
+        _ => panic!("should fail to add a step before the path is started")
+    }
+    match Traversal::new().compile() {
+        Err(QueryBuildFailed(errors)) => assert_eq!(errors, vec!(EmptyPath)),
+        _ => panic!("should fail to compile an empty path")
+    }
+
+    // the broken query is not sent, nobody listens there anyway
+    let graph = Graph::new("127.0.0.1", 1, DefaultVersion).unwrap().with_retry(RetryPolicy::none());
+    match graph.find(V::start(Node("C")).add_step(StartVertex(Unset)).All()) {
+        Err(QueryBuildFailed(errors)) => assert_eq!(errors, vec!(StartedTwice(StartVertex(Unset)))),
+        _ => panic!("should report the build errors before sending the query")
+    }
+
+    // == Morphism names ==
+
+    // the names are declared as JavaScript variables, so nothing else is allowed there
+    match M::start("x = 1; g.V").OutP(Predicate("follows")).compile() {
+        Err(QueryBuildFailed(errors)) => assert_eq!(errors, vec!(InvalidName("x = 1; g.V".to_string()))),
+        _ => panic!("should refuse the name which is not an identifier")
+    }
+
+    path_fail!(M::start(""), "should refuse the empty name");
+    path_fail!(M::start("2friends"), "should refuse the name starting with a digit");
+    path_fail!(M::start("var"), "should refuse the reserved word");
+    path_eq!(M::start("$friend_of_friend2"), "g.M()");
+
+    let mut invalid = M::start("follows)");
+    invalid.OutP(Predicate("follows"));
+    match V::start(Node("C")).Follow(&invalid).All().compile() {
+        Err(QueryBuildFailed(errors)) => match errors[0] {
+            SubQueryFailed(Follow(_), ref failed) =>
+                assert_eq!(*failed, vec!(InvalidName("follows)".to_string()))),
+            _ => panic!("should report the invalid morphism")
+        },
+        _ => panic!("should fail to follow the morphism with an invalid name")
+    }
+
+    match V::start(Node("C")).add_step(FollowR("g.V()".to_string())).All().compile() {
+        Err(QueryBuildFailed(errors)) => assert_eq!(errors, vec!(InvalidName("g.V()".to_string()))),
+        _ => panic!("should refuse the raw step with an invalid name")
+    }
 
-    query.ForEach(callback), query.ForEach(limit, callback); */
+    let mut valid = M::start("follows");
+    valid.OutP(Predicate("follows"));
+    assert_eq!(valid.save_as("follows2").ok(),
+               Some("var follows2 = g.M().Out(\"follows\")".to_string()));
+    assert!(valid.save_as("follows;").is_err());
+    let mut twice = M::start("twice");
+    twice.Follow(&valid).Follow(&valid);
+    assert_eq!(twice.save().ok(),
+               Some("var follows = g.M().Out(\"follows\");\
+                     var twice = g.M().Follow(follows).Follow(follows)".to_string()));
+
+    // the refused steps are not added
+    assert_eq!(V::start(Node("C")).add_step(Final(All)).All().traversal().steps,
+               vec!(StartVertex(Single("C".to_string())), Final(All)));
+
+    // finalizing leaves the path unchanged
+    let mut reused = V::start(Node("C"));
+    reused.OutP(Predicate("follows"));
+    path_eq!(reused.All(), "g.V(\"C\").Out(\"follows\").All()");
+    path_eq!(reused.InP(Predicate("status")).GetLimit(5),
+             "g.V(\"C\").Out(\"follows\").In(\"status\").GetLimit(5)");
 
     /* TODO
 
@@ -0,0 +1,80 @@
+extern crate cayley;
+
+use cayley::shape::{Shape, ShapeNode, ShapeLink};
+use cayley::render;
+
+fn node(id: int, values: Vec<&str>, tags: Vec<&str>, is_link_node: bool, is_fixed: bool) -> ShapeNode {
+    ShapeNode { id: id,
+                values: values.iter().map(|v| v.to_string()).collect(),
+                tags: tags.iter().map(|t| t.to_string()).collect(),
+                is_link_node: is_link_node,
+                is_fixed: is_fixed }
+}
+
+fn link(source: int, target: int, predicate: int, link_node: int) -> ShapeLink {
+    ShapeLink { source: source, target: target, predicate: predicate, link_node: link_node }
+}
+
+#[test]
+fn main() {
+
+    // g.V("Casablanca").In("name").As("movie").In("/film/performance/film").All()
+    let shape = Shape {
+        nodes: vec!(node(0, vec!("Casablanca"), vec!(), false, true),
+                    node(1, vec!("name"), vec!(), false, true),
+                    node(2, vec!(), vec!(), true, false),
+                    node(3, vec!(), vec!("movie"), false, false),
+                    node(4, vec!("/film/performance/film"), vec!(), false, true),
+                    node(5, vec!(), vec!(), true, false),
+                    node(6, vec!(), vec!(), false, false)),
+        links: vec!(link(0, 3, 1, 2),
+                    link(3, 6, 4, 5))
+    };
+
+    assert_eq!(render::to_tree(&shape).as_slice(),
+               "#0 fixed [\"Casablanca\"]\n\
+                `-- [name] #3 tags: [\"movie\"]\n    \
+                    `-- [/film/performance/film] #6\n");
+
+    assert_eq!(render::to_dot(&shape).as_slice(),
+               "digraph shape {\n    \
+                    n0 [label=\"#0\\nCasablanca\", shape=box];\n    \
+                    n1 [label=\"#1\\nname\", shape=box];\n    \
+                    n2 [label=\"#2\", shape=diamond];\n    \
+                    n3 [label=\"#3\\ntags: movie\", shape=ellipse];\n    \
+                    n4 [label=\"#4\\n/film/performance/film\", shape=box];\n    \
+                    n5 [label=\"#5\", shape=diamond];\n    \
+                    n6 [label=\"#6\", shape=ellipse];\n    \
+                    n0 -> n2;\n    \
+                    n2 -> n3;\n    \
+                    n1 -> n2 [style=dashed, label=\"predicate\"];\n    \
+                    n3 -> n5;\n    \
+                    n5 -> n6;\n    \
+                    n4 -> n5 [style=dashed, label=\"predicate\"];\n\
+                }\n");
+
+    // nodes reachable in several ways are expanded once
+    let diamond = Shape {
+        nodes: vec!(node(0, vec!("A"), vec!(), false, true),
+                    node(1, vec!("follows"), vec!(), false, true),
+                    node(2, vec!(), vec!(), true, false),
+                    node(3, vec!(), vec!(), false, false)),
+        links: vec!(link(0, 3, 1, 2),
+                    link(0, 3, 1, 2))
+    };
+
+    assert_eq!(render::to_tree(&diamond).as_slice(),
+               "#0 fixed [\"A\"]\n\
+                +-- [follows] #3\n\
+                `-- [follows] #3 (see above)\n");
+
+    // labels are escaped in DOT output
+    let quoted = Shape { nodes: vec!(node(0, vec!("say \"hi\""), vec!(), false, true)),
+                         links: vec!() };
+
+    assert_eq!(render::to_dot(&quoted).as_slice(),
+               "digraph shape {\n    \
+                    n0 [label=\"#0\\nsay \\\"hi\\\"\", shape=box];\n\
+                }\n");
+
+}
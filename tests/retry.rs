@@ -0,0 +1,94 @@
+extern crate cayley;
+
+use std::time::Duration;
+
+use cayley::errors::GraphResult;
+use cayley::errors::{RequestFailed, ServerError, RetriesExhausted, NetworkError, RejectedByServer};
+use cayley::retry::RetryPolicy;
+
+use support::reset;
+
+mod support;
+
+#[test]
+fn main() {
+
+    let policy = RetryPolicy { max_attempts: 3,
+                               initial_backoff: Duration::milliseconds(100),
+                               max_backoff: Duration::milliseconds(300),
+                               multiplier: 2.0,
+                               jitter: false };
+
+    // == Backoff ==
+
+    assert_eq!(policy.backoff(1), Duration::zero());
+    assert_eq!(policy.backoff(2), Duration::milliseconds(100));
+    assert_eq!(policy.backoff(3), Duration::milliseconds(200));
+    assert_eq!(policy.backoff(4), Duration::milliseconds(300));
+    assert_eq!(policy.backoff(10), Duration::milliseconds(300));
+
+    let instant = RetryPolicy { initial_backoff: Duration::zero(), ..policy.clone() };
+
+    // == Success after failures ==
+
+    let mut made = 0u;
+    let result = instant.run(|attempt| {
+        made = attempt;
+        match attempt {
+            3 => Ok("found"),
+            _ => Err(reset())
+        }
+    });
+    assert_eq!(result.ok(), Some("found"));
+    assert_eq!(made, 3);
+
+    // == Retries exhausted ==
+
+    let mut made = 0u;
+    match instant.run(|attempt| -> GraphResult<()> { made = attempt; Err(reset()) }) {
+        Err(error @ RetriesExhausted(..)) => {
+            assert_eq!(error.attempts(), 3);
+            assert_eq!(error.kind(), NetworkError);
+        },
+        _ => panic!("the last error should be returned after three attempts")
+    }
+    assert_eq!(made, 3);
+
+    // == Not retryable ==
+
+    let mut made = 0u;
+    match instant.run(|attempt| -> GraphResult<()> {
+        made = attempt;
+        Err(ServerError(400, "syntax error".to_string(), "g.V(".to_string()))
+    }) {
+        Err(error @ ServerError(..)) => assert_eq!(error.attempts(), 1),
+        _ => panic!("the rejected query should not be repeated")
+    }
+    assert_eq!(made, 1);
+
+    // the error after a retry still tells how many attempts were made
+    let mut made = 0u;
+    match instant.run(|attempt| -> GraphResult<()> {
+        made = attempt;
+        match attempt {
+            1 => Err(reset()),
+            _ => Err(ServerError(400, "syntax error".to_string(), "g.V(".to_string()))
+        }
+    }) {
+        Err(error) => {
+            assert!(!error.is_retryable());
+            assert_eq!(error.kind(), RejectedByServer);
+            assert_eq!(error.attempts(), 2);
+        },
+        _ => panic!("the rejected query should fail")
+    }
+    assert_eq!(made, 2);
+
+    let mut made = 0u;
+    match RetryPolicy::none().run(|attempt| -> GraphResult<()> { made = attempt; Err(reset()) }) {
+        Err(RequestFailed(..)) => (),
+        _ => panic!("nothing should be repeated without retries")
+    }
+    assert_eq!(made, 1);
+
+}
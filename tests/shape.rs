@@ -0,0 +1,55 @@
+extern crate serialize;
+extern crate cayley;
+
+use serialize::json;
+
+use cayley::shape::{Shape, ShapeNode, ShapeLink};
+
+#[test]
+fn main() {
+
+    // == Shape of g.V("<alice>").Out("<follows>").Tag("friend").All() ==
+
+    // the empty lists are omitted or sent as `null`, the way Cayley does
+    let shape: Shape = json::decode("{\"nodes\": [\
+                                         {\"id\": 1, \"values\": [\"<alice>\"], \
+                                          \"is_link_node\": false, \"is_fixed\": true},\
+                                         {\"id\": 2, \"tags\": null, \"values\": null, \
+                                          \"is_link_node\": true, \"is_fixed\": false},\
+                                         {\"id\": 3, \"tags\": [], \"values\": [\"<follows>\"], \
+                                          \"is_link_node\": false, \"is_fixed\": true},\
+                                         {\"id\": 4, \"tags\": [\"friend\", \"id\"], \
+                                          \"is_link_node\": false, \"is_fixed\": false}\
+                                     ], \"links\": [\
+                                         {\"source\": 1, \"target\": 4, \"type\": 3, \"link_node\": 2}\
+                                     ]}").unwrap();
+
+    assert_eq!(shape.nodes.len(), 4);
+    assert_eq!(shape.nodes[0], ShapeNode { id: 1, tags: vec!(), values: vec!("<alice>".to_string()),
+                                           is_link_node: false, is_fixed: true });
+    assert_eq!(shape.nodes[1], ShapeNode { id: 2, tags: vec!(), values: vec!(),
+                                           is_link_node: true, is_fixed: false });
+    assert_eq!(shape.nodes[2].values, vec!("<follows>".to_string()));
+    assert_eq!(shape.nodes[3].tags, vec!("friend".to_string(), "id".to_string()));
+    assert!(shape.nodes[3].values.is_empty());
+
+    // `type` is the id of the node holding the predicate
+    assert_eq!(shape.links, vec!(ShapeLink { source: 1, target: 4, predicate: 3, link_node: 2 }));
+
+    // == No links ==
+
+    let single: Shape = json::decode("{\"nodes\": [{\"id\": 1, \"is_link_node\": false, \
+                                                    \"is_fixed\": false}]}").unwrap();
+    assert_eq!(single.nodes.len(), 1);
+    assert!(single.links.is_empty());
+    let nulls: Shape = json::decode("{\"nodes\": null, \"links\": null}").unwrap();
+    assert!(nulls.nodes.is_empty() && nulls.links.is_empty());
+
+    // == Errors ==
+
+    // the links must have a predicate
+    assert!(json::decode::<Shape>("{\"nodes\": [], \
+                                    \"links\": [{\"source\": 1, \"target\": 2, \"link_node\": 3}]}")
+                  .is_err());
+
+}
@@ -0,0 +1,88 @@
+extern crate cayley;
+
+use std::io::MemReader;
+
+use cayley::GraphNode;
+use cayley::stream::GraphNodesIter;
+use cayley::errors::{DecodingFailed, ResponseParseFailed};
+
+fn nodes_of(response: &str) -> GraphNodesIter<MemReader> {
+    GraphNodesIter::new(MemReader::new(response.as_bytes().to_vec()), "g.V().All()".to_string())
+}
+
+fn ids(response: &str) -> Vec<String> {
+    nodes_of(response).map(|node| match node {
+        Ok(GraphNode(tags)) => tags["id".to_string()].clone(),
+        Err(error) => panic!(error.to_string())
+    }).collect()
+}
+
+#[test]
+fn main() {
+
+    // == Nodes ==
+
+    let mut nodes = nodes_of("{\"result\": [\
+                                  {\"id\": \"/en/casablanca_1942\", \"name\": \"Casablanca\"},\
+                                  {\"id\": \"/en/alien\", \"year\": 1979}\
+                              ]}");
+
+    match nodes.next() {
+        Some(Ok(GraphNode(casablanca))) => {
+            assert_eq!(casablanca.len(), 2);
+            assert_eq!(casablanca["name".to_string()].as_slice(), "Casablanca");
+        },
+        _ => panic!("the first node was expected")
+    }
+    match nodes.next() {
+        Some(Ok(GraphNode(alien))) => assert_eq!(alien["year".to_string()].as_slice(), "1979"),
+        _ => panic!("the second node was expected")
+    }
+    assert!(nodes.next().is_none());
+    assert!(nodes.next().is_none());
+
+    // .ToArray()
+    assert_eq!(ids("{\"result\": [[\"/en/alien\", \"/en/aliens\"]]}"),
+               vec!("/en/alien".to_string(), "/en/aliens".to_string()));
+
+    // nothing found, `null` if the start node is not in the database at all
+    let mut null = nodes_of("{\"result\": null}");
+    assert!(null.next().is_none());
+    assert!(null.is_null());
+    let mut empty = nodes_of("{\"result\": []}");
+    assert!(empty.next().is_none());
+    assert!(!empty.is_null());
+
+    // == Errors ==
+
+    // the nodes read before the error are given out, and nothing after it
+    let mut broken = nodes_of("{\"result\": [{\"id\": \"/en/alien\"}, {\"id\": ");
+    assert!(broken.next().unwrap().is_ok());
+    match broken.next() {
+        // the error holds the response read so far, not the query
+        Some(Err(DecodingFailed(_, response))) => {
+            assert!(response.as_slice().starts_with("{\"result\": [{\"id\": \"/en/alien\"}"));
+            assert!(response.as_slice().trim_right().ends_with("{\"id\":"));
+        },
+        _ => panic!("the response should fail to be parsed")
+    }
+    assert!(broken.next().is_none());
+
+    // just the end of the long response is kept
+    let mut long = String::from_str("{\"result\": [");
+    for _ in range(0u, 100) { long.push_str("{\"id\": \"/en/alien\"}, "); }
+    long.push_str("{\"id\" ");
+    match nodes_of(long.as_slice()).skip(100).next() {
+        Some(Err(DecodingFailed(_, response))) => {
+            assert_eq!(response.as_slice().char_len(), 256);
+            assert!(long.as_slice().trim_right().ends_with(response.as_slice().trim_right()));
+        },
+        _ => panic!("the long response should fail to be parsed")
+    }
+
+    match nodes_of("{\"result\": [{\"id\": [\"/en/alien\"]}]}").next() {
+        Some(Err(ResponseParseFailed)) => (),
+        _ => panic!("lists should not be read as tag values")
+    }
+
+}
@@ -0,0 +1,166 @@
+#![allow(dead_code)]
+
+// failures shared by the tests of errors, retries and the circuit breaker, and the
+// fake Cayley server for the tests which talk to it
+
+use std::io;
+use std::ascii::StrAsciiExt;
+use std::num::from_str_radix;
+use std::io::{IoError, BufferedStream, Listener, Acceptor};
+use std::io::net::tcp::{TcpListener, TcpStream};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUint, SeqCst};
+
+use cayley::errors::{GraphRequestError, RequestFailed};
+
+pub fn io_error(kind: io::IoErrorKind) -> IoError {
+    IoError { kind: kind, desc: "failed", detail: None }
+}
+
+// the query failed since the connection was reset
+pub fn reset() -> GraphRequestError {
+    RequestFailed(io_error(io::ConnectionReset), "g.V().All()".to_string())
+}
+
+// the query failed since Cayley is not listening
+pub fn refused() -> GraphRequestError {
+    RequestFailed(io_error(io::ConnectionRefused), "g.V().All()".to_string())
+}
+
+// a request read by the fake server
+pub struct Request {
+    // i.e. `POST /api/v1/write HTTP/1.1`
+    pub line: String,
+    pub content_type: Option<String>,
+    pub body: String
+}
+
+// how the fake server replies to a request
+pub enum Reply {
+    // writes the response and waits for the next request
+    Answer(String),
+    // writes the response and closes the connection
+    AnswerAndClose(String),
+    // closes the connection without a response
+    Close,
+    // never answers, the connection is kept until the client closes it
+    Hang
+}
+
+// a fake Cayley server on a free port, it replies to every request the way the given
+// function tells, serves every connection in its own task and stops when dropped
+pub struct FakeCayley {
+    pub port: u16,
+    // every request read, in the order they came
+    pub requests: Receiver<Request>,
+    connections: Arc<AtomicUint>,
+    stop: Sender<()>
+}
+
+impl FakeCayley {
+
+    pub fn start(reply: fn(&Request) -> Reply) -> FakeCayley {
+        let mut listener = TcpListener::bind("127.0.0.1", 0).unwrap();
+        let port = listener.socket_name().unwrap().port;
+        let mut acceptor = listener.listen().unwrap();
+        let (requests, received) = channel();
+        let (stop, stopped) = channel();
+        let connections = Arc::new(AtomicUint::new(0));
+        let accepted = connections.clone();
+        spawn(proc() {
+            loop {
+                acceptor.set_timeout(Some(100));
+                match acceptor.accept() {
+                    Ok(stream) => {
+                        accepted.fetch_add(1, SeqCst);
+                        let requests = requests.clone();
+                        spawn(proc() serve(BufferedStream::new(stream), reply, requests));
+                    },
+                    Err(_) => if stopped.try_recv().is_ok() { break; }
+                }
+            }
+        });
+        FakeCayley { port: port, requests: received, connections: connections, stop: stop }
+    }
+
+    // number of the connections accepted so far
+    pub fn connections(&self) -> uint {
+        self.connections.load(SeqCst)
+    }
+
+}
+
+impl Drop for FakeCayley {
+    fn drop(&mut self) {
+        let _ = self.stop.send_opt(());
+    }
+}
+
+// replies to the requests coming through the connection, until it is closed
+fn serve(mut stream: BufferedStream<TcpStream>, reply: fn(&Request) -> Reply,
+         requests: Sender<Request>) {
+    loop {
+        let request = match next_request(&mut stream) {
+            Some(request) => request,
+            None => return
+        };
+        let replied = reply(&request);
+        let _ = requests.send_opt(request);
+        match replied {
+            Answer(response) => respond(&mut stream, response.as_slice()),
+            AnswerAndClose(response) => { respond(&mut stream, response.as_slice()); return; },
+            Close => return,
+            Hang => ()
+        }
+    }
+}
+
+// a complete response with the given status line and body
+pub fn http_response(status: &str, body: &str) -> String {
+    format!("HTTP/1.1 {:s}\r\nContent-Length: {:u}\r\n\r\n{:s}", status, body.len(), body)
+}
+
+// reads the next request, `None` if the connection was closed before it
+fn next_request(stream: &mut BufferedStream<TcpStream>) -> Option<Request> {
+    let line = match stream.read_line() {
+        Ok(line) => line.as_slice().trim_right().to_string(),
+        Err(_) => return None
+    };
+    let mut content_type = None;
+    let mut length = 0u;
+    let mut chunked = false;
+    loop {
+        let line = stream.read_line().unwrap();
+        let header = line.as_slice().trim_right();
+        if header.is_empty() { break; }
+        let lowercase = header.to_ascii_lower();
+        if lowercase.as_slice().starts_with("content-length: ") {
+            length = from_str(header.slice_from(16)).unwrap();
+        } else if lowercase.as_slice().starts_with("content-type: ") {
+            content_type = Some(header.slice_from(14).to_string());
+        } else if lowercase.as_slice() == "transfer-encoding: chunked" {
+            chunked = true;
+        }
+    }
+    let body = match chunked {
+        false => stream.read_exact(length).unwrap(),
+        true => {
+            let mut body = Vec::new();
+            loop {
+                let size_line = stream.read_line().unwrap();
+                let size = from_str_radix::<uint>(size_line.as_slice().trim_right(), 16).unwrap();
+                if size == 0 { stream.read_line().unwrap(); break; }
+                body.push_all(stream.read_exact(size).unwrap().as_slice());
+                stream.read_line().unwrap();
+            }
+            body
+        }
+    };
+    Some(Request { line: line, content_type: content_type,
+                   body: String::from_utf8(body).unwrap() })
+}
+
+fn respond(stream: &mut BufferedStream<TcpStream>, response: &str) {
+    stream.write_str(response).unwrap();
+    stream.flush().unwrap();
+}
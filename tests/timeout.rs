@@ -0,0 +1,49 @@
+extern crate cayley;
+
+use std::time::Duration;
+use std::default::Default;
+
+use cayley::{Graph, DefaultVersion, GraphConfig};
+use cayley::errors::{Timeout, TimedOut};
+use cayley::retry::RetryPolicy;
+
+use support::{FakeCayley, Request, Reply, Hang};
+
+mod support;
+
+// the queries are read, but never answered
+fn silent(_: &Request) -> Reply { Hang }
+
+#[test]
+fn main() {
+
+    let server = FakeCayley::start(silent);
+    let port = server.port;
+
+    // == Read timeout ==
+
+    let graph = Graph::new("127.0.0.1", port, DefaultVersion).unwrap()
+                      .with_retry(RetryPolicy::none())
+                      .with_config(GraphConfig { read_timeout: Some(Duration::milliseconds(100)),
+                                                 ..Default::default() });
+    match graph.exec_json("g.V().All()".to_string()) {
+        Err(error @ Timeout(..)) => {
+            assert_eq!(error.kind(), TimedOut);
+            assert_eq!(error.to_string(), "Source(\"g.V().All()\"): Timed out".to_string());
+        },
+        Err(error) => panic!("the query should time out, not fail with {}", error),
+        Ok(_) => panic!("the query should time out")
+    }
+
+    // == Deadline ==
+
+    let graph = Graph::new("127.0.0.1", port, DefaultVersion).unwrap()
+                      .with_retry(RetryPolicy::none())
+                      .with_config(GraphConfig { deadline: Some(Duration::milliseconds(100)),
+                                                 ..Default::default() });
+    match graph.exec_json("g.V().All()".to_string()) {
+        Err(Timeout(query)) => assert_eq!(query.as_slice(), "g.V().All()"),
+        _ => panic!("the query should time out")
+    }
+
+}
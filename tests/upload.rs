@@ -0,0 +1,100 @@
+extern crate cayley;
+
+use std::io;
+use std::io::{IoResult, MemReader, MemWriter};
+use std::cmp;
+
+use cayley::UploadProgress;
+use cayley::upload::{stream_multipart, stream_raw, SourceFailed};
+
+// gives out the data a few bytes at a time, so the statements are split between the chunks
+struct Trickle {
+    data: Vec<u8>,
+    position: uint
+}
+
+impl Reader for Trickle {
+    fn read(&mut self, buffer: &mut [u8]) -> IoResult<uint> {
+        if self.position >= self.data.len() { return Err(io::standard_error(io::EndOfFile)); }
+        let len = cmp::min(cmp::min(buffer.len(), 4), self.data.len() - self.position);
+        for i in range(0, len) { buffer[i] = self.data[self.position + i]; }
+        self.position += len;
+        Ok(len)
+    }
+}
+
+struct Broken;
+
+impl Reader for Broken {
+    fn read(&mut self, _: &mut [u8]) -> IoResult<uint> {
+        Err(io::standard_error(io::OtherIoError))
+    }
+}
+
+#[test]
+fn main() {
+
+    // == Counting quads ==
+
+    let source = "# comment .\n\
+                  <a> <b> <c> .\n\
+                  \n  \t# indented comment <x> <y> <z> .\n\
+                  \x20 <d> <e> <f> .\r\n\
+                  <g> <h> <i> .";
+    let mut target = MemWriter::new();
+    let mut reported = Vec::new();
+    let sent = stream_raw(&mut Trickle { data: source.as_bytes().to_vec(), position: 0 },
+                          &mut target, |progress| reported.push(progress));
+    assert_eq!(sent.ok(), Some(UploadProgress { bytes: source.len() as u64, quads: 3 }));
+
+    // every chunk is reported, and then the last statement, which has no line end
+    let chunks = (source.len() + 3) / 4;
+    assert_eq!(reported.len(), chunks + 1);
+    assert_eq!(reported[chunks - 1], UploadProgress { bytes: source.len() as u64, quads: 2 });
+    assert_eq!(reported[0], UploadProgress { bytes: 4, quads: 0 });
+    assert!(reported.iter().zip(reported.iter().skip(1)).all(|(a, b)| a.quads <= b.quads));
+
+    // == Chunks ==
+
+    let mut expected = String::new();
+    for piece in source.as_bytes().chunks(4) {
+        expected.push_str(format!("{:x}\r\n", piece.len()).as_slice());
+        expected.push_str(std::str::from_utf8(piece).unwrap());
+        expected.push_str("\r\n");
+    }
+    expected.push_str("0\r\n\r\n");
+    assert_eq!(String::from_utf8(target.unwrap()).unwrap(), expected);
+
+    // empty source
+    let mut target = MemWriter::new();
+    let sent = stream_raw(&mut MemReader::new(Vec::new()), &mut target, |_| ());
+    assert_eq!(sent.ok(), Some(UploadProgress { bytes: 0, quads: 0 }));
+    assert_eq!(target.unwrap(), b"0\r\n\r\n".to_vec());
+
+    // == Multipart ==
+
+    let mut target = MemWriter::new();
+    let sent = stream_multipart(&mut MemReader::new(b"<a> <b> <c> .\n".to_vec()), &mut target,
+                                "b0undary", "my \"quads\".nq", |_| ());
+    assert_eq!(sent.ok(), Some(UploadProgress { bytes: 14, quads: 1 }));
+    assert_eq!(String::from_utf8(target.unwrap()).unwrap(),
+               "82\r\n\
+                --b0undary\r\n\
+                Content-Disposition: form-data; name=\"NQuadFile\"; filename=\"my 'quads'.nq\"\r\n\
+                Content-Type: application/octet-stream\r\n\r\n\
+                \r\n\
+                e\r\n\
+                <a> <b> <c> .\n\r\n\
+                10\r\n\
+                \r\n--b0undary--\r\n\
+                \r\n\
+                0\r\n\r\n".to_string());
+
+    // == Failures ==
+
+    match stream_raw(&mut Broken, &mut MemWriter::new(), |_| ()) {
+        Err(SourceFailed(error)) => assert_eq!(error.kind, io::OtherIoError),
+        _ => panic!("the source failure should be reported")
+    }
+
+}
@@ -0,0 +1,155 @@
+extern crate serialize;
+extern crate cayley;
+
+use std::io::MemReader;
+
+use serialize::json;
+
+use cayley::{Graph, V1, V2, Quad, Written, Refused};
+use cayley::errors::{ServerError, NotSupported, InvalidRequest};
+use cayley::mql;
+use cayley::mql::Mql;
+use cayley::path::{Vertex, Query};
+use cayley::selector::AnyNode;
+
+use support::{FakeCayley, Request, Reply, Answer, http_response};
+
+mod support;
+
+// answers the way Cayley does with V2 API, depending on the query
+fn reply(request: &Request) -> Reply {
+    let (status, body) = match (request.line.as_slice().words().nth(1).unwrap(),
+                                request.body.as_slice()) {
+        (_, "g.V(") => ("400 Bad Request", "{\"error\": \"Unexpected token\"}"),
+        // a caching proxy may answer with other successful status than `200`
+        (_, "g.V().GetLimit(1)") => ("203 Non-Authoritative Information",
+                                     "{\"result\": [{\"id\": \"<alice>\"}]}"),
+        (_, "{ nodes { id ") => ("400 Bad Request",
+                                 "{\"errors\": [{\"message\": \"Syntax Error: Expected }\"}]}"),
+        (_, "{ nodes(id: \"<nobody>\") { id, friends { id } } }") =>
+            ("200 OK", "{\"data\": null, \"errors\": [{\"message\": \"no friends\"}, \"no id\"]}"),
+        ("/api/v2/query?lang=graphql", _) =>
+            ("200 OK", "{\"data\": {\"nodes\": [{\"id\": \"<alice>\"}]}}"),
+        ("/api/v2/query?lang=gremlin", _) | ("/api/v2/query?lang=mql", _) =>
+            ("200 OK", "{\"result\": [{\"id\": \"<alice>\"}]}"),
+        ("/api/v1/shape/gremlin", _) => ("200 OK", "{\"nodes\": [], \"links\": []}"),
+        ("/api/v2/write", "[]") => ("400 Bad Request", "{\"errors\": [\"no quads\"]}"),
+        ("/api/v2/write", _) => ("200 OK", "{\"result\": \"Successfully wrote 1 quads.\", \"count\": 1}"),
+        ("/api/v2/delete", _) =>
+            ("200 OK", "{\"result\": \"Successfully deleted 1 quads.\", \"count\": 1}"),
+        (_, _) => ("404 Not Found", "{\"error\": \"not found\"}")
+    };
+    Answer(http_response(status, body))
+}
+
+// the request line, the content type and the body of the next request
+fn next(server: &FakeCayley) -> (String, Option<String>, String) {
+    let Request { line, content_type, body } = server.requests.recv();
+    (line, content_type, body)
+}
+
+#[test]
+fn main() {
+
+    let server = FakeCayley::start(reply);
+    let port = server.port;
+
+    let graph = Graph::new("127.0.0.1", port, V2).unwrap();
+
+    // == Queries ==
+
+    assert!(graph.exec_json("g.V().All()".to_string()).is_ok());
+    assert_eq!(next(&server), ("POST /api/v2/query?lang=gremlin HTTP/1.1".to_string(), None,
+                                 "g.V().All()".to_string()));
+
+    let query = Mql::new(json::List(vec!(mql::object(vec!(("id", mql::any()))))));
+    assert!(graph.find_mql(&query).is_ok());
+    assert_eq!(next(&server), ("POST /api/v2/query?lang=mql HTTP/1.1".to_string(), None,
+                                 "[{\"id\":null}]".to_string()));
+
+    match graph.exec_json("g.V(".to_string()) {
+        Err(ServerError(400, message, query)) => {
+            assert_eq!(message.as_slice(), "Unexpected token");
+            assert_eq!(query.as_slice(), "g.V(");
+        },
+        _ => panic!("the rejected query should fail with the message of the server")
+    }
+    next(&server);
+
+    assert!(graph.exec_json("g.V().GetLimit(1)".to_string()).is_ok());
+    next(&server);
+    let mut nodes = graph.find_iter(Vertex::start(AnyNode).GetLimit(1)).unwrap();
+    assert!(nodes.next().is_some());
+    assert!(nodes.next().is_none());
+    next(&server);
+
+    // shape API has no V2 alternative
+    assert!(graph.shape(Vertex::start(AnyNode).All()).is_ok());
+    assert_eq!(next(&server), ("POST /api/v1/shape/gremlin HTTP/1.1".to_string(), None,
+                                 "g.V().All()".to_string()));
+
+    // == GraphQL ==
+
+    match graph.find_graphql("{ nodes { id } }") {
+        Ok(data) => assert_eq!(data.to_string(), "{\"nodes\":[{\"id\":\"<alice>\"}]}".to_string()),
+        Err(error) => panic!("GraphQL query failed with {}", error)
+    }
+    assert_eq!(next(&server), ("POST /api/v2/query?lang=graphql HTTP/1.1".to_string(), None,
+                                 "{ nodes { id } }".to_string()));
+
+    match graph.find_graphql("{ nodes { id ") {
+        Err(ServerError(400, message, _)) => assert_eq!(message.as_slice(), "Syntax Error: Expected }"),
+        _ => panic!("the rejected GraphQL query should fail with the message of the server")
+    }
+    next(&server);
+
+    // the errors may come along with the data
+    match graph.find_graphql("{ nodes(id: \"<nobody>\") { id, friends { id } } }") {
+        Err(ServerError(_, message, _)) => assert_eq!(message.as_slice(), "no friends; no id"),
+        _ => panic!("the errors reported with the data should not be lost")
+    }
+    next(&server);
+
+    // there is no GraphQL in V1, so the query is not sent at all
+    let old = Graph::new("127.0.0.1", port, V1).unwrap();
+    match old.find_graphql("{ nodes { id } }") {
+        Err(error @ NotSupported(_)) => assert_eq!(error.kind(), InvalidRequest),
+        _ => panic!("GraphQL query should not be sent to V1 graph")
+    }
+    assert!(server.requests.try_recv().is_err());
+    drop(old);
+
+    // == Writes ==
+
+    let quads = [Quad::new("<alice>", "<follows>", "<bob>")];
+
+    match graph.write(quads.as_slice()) {
+        Ok(Written(message)) => assert_eq!(message.as_slice(), "Successfully wrote 1 quads."),
+        _ => panic!("the quads should be written")
+    }
+    assert_eq!(next(&server), ("POST /api/v2/write HTTP/1.1".to_string(),
+                                 Some("application/json".to_string()),
+                                 json::encode(&quads.as_slice())));
+
+    match graph.write(&[]) {
+        Ok(Refused(message)) => assert_eq!(message.as_slice(), "no quads"),
+        _ => panic!("the write should be refused with the message of the server")
+    }
+    next(&server);
+
+    match graph.delete(quads.as_slice()) {
+        Ok(Written(message)) => assert_eq!(message.as_slice(), "Successfully deleted 1 quads."),
+        _ => panic!("the quads should be deleted")
+    }
+    let (line, content_type, _) = next(&server);
+    assert_eq!(line.as_slice(), "POST /api/v2/delete HTTP/1.1");
+    assert_eq!(content_type, Some("application/json".to_string()));
+
+    // N-Quads are uploaded as they are, without a multipart form
+    let document = "<alice> <follows> <bob> .\n";
+    assert!(graph.write_reader(&mut MemReader::new(document.as_bytes().to_vec()), |_| ()).is_ok());
+    assert_eq!(next(&server), ("POST /api/v2/write HTTP/1.1".to_string(),
+                                 Some("application/n-quads".to_string()),
+                                 document.to_string()));
+
+}
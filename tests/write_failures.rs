@@ -0,0 +1,81 @@
+extern crate cayley;
+
+use std::time::Duration;
+use std::default::Default;
+use std::sync::atomic::{AtomicUint, INIT_ATOMIC_UINT, SeqCst};
+
+use cayley::{Graph, DefaultVersion, Quad, Written, Refused};
+use cayley::errors::{ServerError, RejectedByServer};
+use cayley::retry::RetryPolicy;
+
+use support::{FakeCayley, Request, Reply, Answer, http_response};
+
+mod support;
+
+static FLAKY_FAILURES: AtomicUint = INIT_ATOMIC_UINT;
+
+fn reply(request: &Request) -> Reply {
+    let body = request.body.as_slice();
+    if body.contains("flaky") {
+        // a proxy in front of Cayley fails once, with a plain text
+        match FLAKY_FAILURES.fetch_add(1, SeqCst) {
+            0 => Answer(http_response("503 Service Unavailable", "Service Unavailable")),
+            _ => Answer(http_response("200 OK", "{\"result\": \"Successfully wrote 1 triples.\"}"))
+        }
+    } else if body.contains("broken") {
+        Answer(http_response("500 Internal Server Error", "{\"error\": \"disk is full\"}"))
+    } else {
+        Answer(http_response("400 Bad Request", "{\"error\": \"invalid quad\"}"))
+    }
+}
+
+fn quads(object: &str) -> Vec<Quad> {
+    vec!(Quad::new("foo", "follows", object))
+}
+
+#[test]
+fn main() {
+
+    let server = FakeCayley::start(reply);
+    let graph = Graph::new("127.0.0.1", server.port, DefaultVersion).unwrap()
+                      .with_retry(RetryPolicy { initial_backoff: Duration::zero(),
+                                                ..Default::default() });
+
+    // == Idempotent writes ==
+
+    match graph.write_idempotent(quads("flaky").as_slice()) {
+        Ok(Written(message)) => assert_eq!(message.as_slice(), "Successfully wrote 1 triples."),
+        _ => panic!("the write should be repeated after the server failed")
+    }
+    server.requests.recv();
+    server.requests.recv();
+
+    match graph.delete_idempotent(quads("broken").as_slice()) {
+        Err(error) => {
+            assert_eq!(error.kind(), RejectedByServer);
+            assert_eq!(error.attempts(), 3);
+        },
+        Ok(_) => panic!("the delete should fail while the server fails")
+    }
+    for _ in range(0u, 3) { server.requests.recv(); }
+
+    // == Other writes ==
+
+    match graph.write(quads("broken").as_slice()) {
+        Err(error @ ServerError(500, _, _)) => {
+            assert!(error.is_retryable());
+            assert_eq!(error.attempts(), 1);
+        },
+        _ => panic!("the failure of the server should not be read as a refused write")
+    }
+    server.requests.recv();
+
+    match graph.write(quads("bar").as_slice()) {
+        Ok(Refused(message)) => assert_eq!(message.as_slice(), "invalid quad"),
+        _ => panic!("the invalid write should be refused")
+    }
+    server.requests.recv();
+
+    assert!(server.requests.try_recv().is_err());
+
+}
@@ -0,0 +1,35 @@
+extern crate cayley;
+
+use cayley::{Graph, DefaultVersion, Quad};
+
+use support::{FakeCayley, Request, Reply, Answer, Close, http_response};
+
+mod support;
+
+fn reply(request: &Request) -> Reply {
+    match request.body.as_slice() {
+        // the query leaves the connection in the pool
+        "g.Emit(1)" => Answer(http_response("200 OK", "{\"result\": 1}")),
+        // the write is read, and the connection is reset before the answer
+        _ => Close
+    }
+}
+
+#[test]
+fn main() {
+
+    let server = FakeCayley::start(reply);
+
+    // the default retry policy is used, but it never repeats the writes
+    let graph = Graph::new("127.0.0.1", server.port, DefaultVersion).unwrap();
+
+    assert!(graph.exec_json("g.Emit(1)".to_string()).is_ok());
+    server.requests.recv();
+
+    assert!(graph.write([Quad::new("foo", "follows", "bar")].as_slice()).is_err());
+    assert!(server.requests.recv().body.as_slice().contains("follows"));
+    // neither the pool nor the retry policy sends the write once again
+    assert_eq!(server.connections(), 1);
+    assert!(server.requests.try_recv().is_err());
+
+}